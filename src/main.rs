@@ -1,6 +1,20 @@
-use std::collections::HashMap;
+// Ces fonctions forment une petite bibliothèque dont `main` n'utilise qu'une partie.
+#![allow(dead_code)]
+
+// Fournit l'implémentation BLAS utilisée par ndarray
+#[cfg(feature = "blas")]
+extern crate blas_src;
+
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 /// Génère un vecteur de taille fixe avec des valeurs aléatoires entre 0 et 1.
 /// 
@@ -54,105 +68,8422 @@ fn calcul_similarite(vec1: &[f32], vec2: &[f32]) -> f32 {
     }
 }
 
-/// Structure représentant une base de données simple avec des UUIDs associés à des vecteurs d'embarquement.
+/// Calcule la similarité cosinus entre une requête fournie sous forme d'itérateur et un vecteur stocké.
 /// 
-/// La structure permet d'insérer des vecteurs et de rechercher les vecteurs les plus similaires à une requête donnée.
+/// Cette variante de `calcul_similarite` évite de matérialiser la requête dans un `Vec` lorsqu'elle
+/// est produite à la volée. L'itérateur est parcouru deux fois (une fois pour sa norme, une fois pour
+/// le produit scalaire), d'où la contrainte `Clone`.
 /// 
-/// # Champs
+/// # Arguments
+/// 
+/// * `query` - Un itérateur produisant les composantes de la requête.
+/// * `stored` - Le vecteur stocké sous forme de slice de `f32`.
+/// 
+/// # Retour
+/// 
+/// Retourne la similarité cosinus, ou `0.0` si l'un des deux vecteurs a une norme nulle.
+/// 
+/// # Exemple
 /// 
-/// * `inner` - Un `HashMap` qui associe un identifiant unique (UUID) à un vecteur d'embarquement (`Vec<f32>`).
-struct Db {
-    inner: HashMap<Uuid, Vec<f32>>,
+/// ```
+/// let similarity = cosine_iter((0..768).map(|i| i as f32), &embedding);
+/// ```
+fn cosine_iter<I: Iterator<Item = f32> + Clone>(query: I, stored: &[f32]) -> f32 {
+    let norme_query: f32 = query.clone().map(|x| x * x).sum::<f32>().sqrt();
+    let produit: f32 = query.zip(stored).map(|(a, b)| a * b).sum();
+    let norme_stored: f32 = stored.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norme_query == 0.0 || norme_stored == 0.0 {
+        0.0 // Éviter la division par zéro
+    } else {
+        produit / (norme_query * norme_stored)
+    }
 }
 
-impl Db {
-    /// Crée une nouvelle base de données vide.
-    /// 
-    /// # Retour
+/// Calcule la similarité cosinus entre deux vecteurs en ne tenant compte que de certaines dimensions.
+/// 
+/// Seules les dimensions pour lesquelles `mask` vaut `true` entrent dans le produit scalaire et dans
+/// les normes : les autres (remplissage, bruit…) sont ignorées comme si elles n'existaient pas.
+/// 
+/// # Arguments
+/// 
+/// * `a` - Le premier vecteur.
+/// * `b` - Le deuxième vecteur.
+/// * `mask` - Les dimensions à prendre en compte.
+/// 
+/// # Retour
+/// 
+/// Retourne la similarité cosinus sur le sous-espace sélectionné, ou `0.0` si l'un des vecteurs y a
+/// une norme nulle.
+/// 
+/// # Panique
+/// 
+/// Panique si `a`, `b` et `mask` n'ont pas tous la même longueur.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// // Les deux dernières dimensions sont du remplissage
+/// let similarity = cosine_masquee(&[1.0, 2.0, 9.0, 9.0], &[1.0, 2.0, -9.0, 0.0], &[true, true, false, false]);
+/// assert!((similarity - 1.0).abs() < 1e-6);
+/// ```
+fn cosine_masquee(a: &[f32], b: &[f32], mask: &[bool]) -> f32 {
+    assert_eq!(a.len(), b.len(), "les vecteurs doivent avoir la même dimension");
+    assert_eq!(a.len(), mask.len(), "le masque doit avoir la dimension des vecteurs");
+
+    let (mut produit, mut norme_a, mut norme_b) = (0.0f32, 0.0f32, 0.0f32);
+    for ((x, y), _) in a.iter().zip(b).zip(mask).filter(|(_, &garde)| garde) {
+        produit += x * y;
+        norme_a += x * x;
+        norme_b += y * y;
+    }
+
+    if norme_a == 0.0 || norme_b == 0.0 {
+        0.0 // Éviter la division par zéro
+    } else {
+        produit / (norme_a.sqrt() * norme_b.sqrt())
+    }
+}
+
+/// Calcule la similarité cosinus entre deux vecteurs en pondérant chaque dimension.
+/// 
+/// Chaque terme des sommes du cosinus est multiplié par le poids de sa dimension :
+/// `Σ wᵢ qᵢ xᵢ / (√(Σ wᵢ qᵢ²) · √(Σ wᵢ xᵢ²))`. Avec des poids tous égaux, on retrouve le cosinus
+/// habituel ; un poids nul fait disparaître la dimension, comme un `false` de `cosine_masquee`.
+/// 
+/// # Arguments
+/// 
+/// * `query` - Le vecteur de requête.
+/// * `stored` - Le vecteur stocké auquel la requête est comparée.
+/// * `poids` - Le poids, positif ou nul, de chaque dimension.
+/// 
+/// # Retour
+/// 
+/// Retourne la similarité cosinus pondérée, ou `0.0` si l'un des vecteurs a une norme pondérée nulle.
+/// 
+/// # Panique
+/// 
+/// Panique si `query`, `stored` et `poids` n'ont pas tous la même longueur.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let uniforme = cosine_pondere(&[1.0, 2.0], &[2.0, 1.0], &[3.0, 3.0]);
+/// assert!((uniforme - calcul_similarite(&[1.0, 2.0], &[2.0, 1.0])).abs() < 1e-6);
+/// ```
+fn cosine_pondere(query: &[f32], stored: &[f32], poids: &[f32]) -> f32 {
+    assert_eq!(query.len(), stored.len(), "les vecteurs doivent avoir la même dimension");
+    assert_eq!(query.len(), poids.len(), "il faut un poids par dimension des vecteurs");
+
+    let (mut produit, mut norme_q, mut norme_x) = (0.0f32, 0.0f32, 0.0f32);
+    for ((q, x), w) in query.iter().zip(stored).zip(poids) {
+        produit += w * q * x;
+        norme_q += w * q * q;
+        norme_x += w * x * x;
+    }
+
+    if norme_q <= 0.0 || norme_x <= 0.0 {
+        0.0 // Éviter la division par zéro
+    } else {
+        produit / (norme_q.sqrt() * norme_x.sqrt())
+    }
+}
+
+/// Calcule les dimensions qui contribuent le plus au produit scalaire entre deux vecteurs.
+/// 
+/// La contribution de la dimension `i` vaut `query[i] * stored[i]` : c'est le terme qu'elle ajoute au
+/// produit scalaire (et donc, à normalisation près, à la similarité cosinus). Les dimensions sont
+/// classées par valeur absolue décroissante de leur contribution, ce qui fait ressortir aussi bien
+/// celles qui rapprochent les deux vecteurs que celles qui les éloignent.
+/// 
+/// # Arguments
+/// 
+/// * `query` - Le vecteur de requête.
+/// * `stored` - Le vecteur stocké auquel la requête est comparée.
+/// * `top` - Le nombre maximal de dimensions à retourner.
+/// 
+/// # Retour
+/// 
+/// Retourne au plus `top` tuples `(indice, contribution)`, triés par magnitude décroissante. En cas
+/// d'égalité, la dimension d'indice le plus petit vient en premier.
+/// 
+/// # Panique
+/// 
+/// Panique si les deux vecteurs n'ont pas la même dimension.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// // 1*4 = 4, 2*(-5) = -10, 3*0 = 0
+/// let top = contributions(&[1.0, 2.0, 3.0], &[4.0, -5.0, 0.0], 2);
+/// assert_eq!(top, vec![(1, -10.0), (0, 4.0)]);
+/// ```
+fn contributions(query: &[f32], stored: &[f32], top: usize) -> Vec<(usize, f32)> {
+    assert_eq!(query.len(), stored.len(), "les vecteurs doivent avoir la même dimension");
+
+    let mut termes: Vec<(usize, f32)> = query
+        .iter()
+        .zip(stored)
+        .map(|(a, b)| a * b)
+        .enumerate()
+        .collect();
+
+    // Trier par magnitude décroissante, puis par indice pour un résultat déterministe
+    termes.sort_by(|a, b| {
+        b.1.abs()
+            .partial_cmp(&a.1.abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+
+    termes.into_iter().take(top).collect()
+}
+
+/// Calcule le produit scalaire entre deux vecteurs.
+/// 
+/// Contrairement à la similarité cosinus, le résultat n'est pas normalisé : il dépend de la norme
+/// des deux vecteurs, ce qui est voulu lorsqu'elle porte de l'information (une confiance, par
+/// exemple).
+/// 
+/// # Arguments
+/// 
+/// * `a` - Le premier vecteur.
+/// * `b` - Le deuxième vecteur.
+/// 
+/// # Retour
+/// 
+/// Retourne `Σ aᵢ bᵢ`. Comme pour `calcul_similarite`, seules les composantes communes sont prises
+/// en compte si les longueurs diffèrent.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// assert_eq!(produit_scalaire(&[1.0, 2.0, 3.0], &[4.0, -5.0, 6.0]), 12.0);
+/// ```
+fn produit_scalaire(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Calcule la distance euclidienne entre deux vecteurs.
+fn distance_euclidienne(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Calcule la norme euclidienne d'un vecteur.
+fn norme(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Indique si deux vecteurs sont égaux à une tolérance près.
+/// 
+/// # Arguments
+/// 
+/// * `a` - Le premier vecteur.
+/// * `b` - Le second vecteur.
+/// * `eps` - L'écart maximal toléré sur chaque composante.
+/// 
+/// # Retour
+/// 
+/// Retourne `true` si les vecteurs ont la même longueur et que chaque composante de `a` diffère de
+/// celle de `b` d'au plus `eps`, sinon `false`.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// assert!(approx_egal(&[1.0, 2.0], &[1.0005, 1.9995], 1e-3));
+/// assert!(!approx_egal(&[1.0, 2.0], &[1.0], 1e-3));
+/// ```
+fn approx_egal(a: &[f32], b: &[f32], eps: f32) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() <= eps)
+}
+
+/// Mesure utilisée pour comparer un vecteur de requête aux vecteurs stockés.
+/// 
+/// * `Cosine` - La similarité cosinus (`calcul_similarite`).
+/// * `DotProduct` - Le produit scalaire brut, sans normalisation.
+/// * `Euclidean` - La distance euclidienne.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+    Cosine,
+    DotProduct,
+    Euclidean,
+}
+
+impl Metric {
+    /// Calcule le score de `b` par rapport à `a` selon la mesure.
     /// 
-    /// Retourne une instance de `Db` initialisée avec un `HashMap` vide.
-    fn new() -> Self {
-        Db {
-            inner: HashMap::new(),
+    /// Un score plus élevé signifie toujours des vecteurs plus proches : pour la distance
+    /// euclidienne, le score est donc l'opposé de la distance.
+    fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => calcul_similarite(a, b),
+            Metric::DotProduct => produit_scalaire(a, b),
+            Metric::Euclidean => -distance_euclidienne(a, b),
         }
     }
 
-    /// Insère un vecteur d'embarquement dans la base de données associé à un UUID unique.
-    /// 
-    /// # Arguments
+    /// Calcule la distance entre `a` et `b` selon la mesure.
     /// 
-    /// * `uuid` - L'UUID unique à associer au vecteur.
-    /// * `embedding` - Le vecteur d'embarquement à insérer.
-    fn insert(&mut self, uuid: Uuid, embedding: Vec<f32>) {
-        self.inner.insert(uuid, embedding);
+    /// Une distance plus faible signifie des vecteurs plus proches : `1 - cosinus` pour `Cosine`,
+    /// la distance euclidienne pour `Euclidean`, et l'opposé du produit scalaire pour `DotProduct`.
+    /// Cette dernière n'est pas une vraie distance (elle peut être négative) et convient mal aux
+    /// algorithmes qui supposent des distances positives.
+    fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        match self {
+            Metric::Cosine => 1.0 - calcul_similarite(a, b),
+            Metric::DotProduct => -produit_scalaire(a, b),
+            Metric::Euclidean => distance_euclidienne(a, b),
+        }
     }
+}
 
-    /// Trouve les `n` vecteurs les plus similaires à un vecteur de requête donné.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `query` - Le vecteur de requête pour lequel on recherche les vecteurs les plus similaires.
-    /// * `n` - Le nombre de résultats à retourner.
-    /// 
-    /// # Retour
-    /// 
-    /// Retourne un vecteur de tuples `(Uuid, f32)` où chaque tuple contient l'UUID d'un vecteur
-    /// et la similarité cosinus entre ce vecteur et la requête.
-    /// 
-    /// # Exemple
-    /// 
-    /// ```
-    /// let resultats = db.trouver_les_plus_similaires(&query_embedding, 3);
-    /// ```
-    fn trouver_les_plus_similaires(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
-        let mut similarities: Vec<(Uuid, f32)> = self
-            .inner
-            .iter()
-            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
-            .collect();
+/// Calcule la divergence de Jensen-Shannon entre deux distributions de probabilité.
+/// 
+/// La divergence vaut `JS(a, b) = ½ KL(a ‖ m) + ½ KL(b ‖ m)` avec `m = ½ (a + b)`. Elle est
+/// calculée en base 2 et est donc comprise entre `0.0` (distributions identiques) et `1.0`
+/// (supports disjoints). Les cases de probabilité nulle ne contribuent pas (`0 · log 0 = 0`), ce
+/// qui évite tout logarithme de zéro.
+/// 
+/// # Arguments
+/// 
+/// * `a` - La première distribution.
+/// * `b` - La deuxième distribution.
+/// 
+/// # Retour
+/// 
+/// Retourne la divergence de Jensen-Shannon sous forme de `f32`.
+/// 
+/// # Panique
+/// 
+/// Panique si les deux vecteurs n'ont pas la même dimension, ou si l'un d'eux n'est pas une
+/// distribution de probabilité (composante négative, ou somme s'écartant de 1 de plus de `1e-3`).
+/// 
+/// # Exemple
+/// 
+/// ```
+/// assert_eq!(jensen_shannon(&[0.5, 0.5], &[0.5, 0.5]), 0.0);
+/// assert_eq!(jensen_shannon(&[1.0, 0.0], &[0.0, 1.0]), 1.0);
+/// ```
+fn jensen_shannon(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "les distributions doivent avoir la même dimension");
+    for distribution in [a, b] {
+        assert!(
+            distribution.iter().all(|&p| p >= 0.0),
+            "une distribution ne peut pas avoir de probabilité négative"
+        );
+        let somme: f32 = distribution.iter().sum();
+        assert!((somme - 1.0).abs() <= 1e-3, "une distribution doit sommer à 1 (somme : {})", somme);
+    }
 
-        // Trier par similarité décroissante
-        similarities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    // Terme p · log2(p / m), nul lorsque p est nul
+    let terme = |p: f32, m: f32| if p > 0.0 { p * (p / m).log2() } else { 0.0 };
 
-        // Retourner les n premiers
-        similarities.into_iter().take(n).collect()
+    let divergence: f32 = a
+        .iter()
+        .zip(b)
+        .map(|(&p, &q)| {
+            let m = 0.5 * (p + q);
+            0.5 * terme(p, m) + 0.5 * terme(q, m)
+        })
+        .sum();
+
+    // Les erreurs d'arrondi peuvent produire une valeur très légèrement négative
+    divergence.max(0.0)
+}
+
+/// Résultat d'un partitionnement des vecteurs stockés, produit par `Db::kmeans`.
+/// 
+/// # Champs
+/// 
+/// * `affectations` - Le groupe (indice dans `centroides`) de chaque UUID.
+/// * `centroides` - Le centre de chaque groupe.
+/// * `inertie` - La somme des distances euclidiennes au carré entre chaque vecteur et son centre.
+#[derive(Debug, Clone)]
+struct Partitionnement {
+    affectations: HashMap<Uuid, usize>,
+    centroides: Vec<Vec<f32>>,
+    inertie: f32,
+}
+
+/// Choisit `k` centres initiaux par la méthode k-means++.
+/// 
+/// Le premier centre est tiré uniformément ; chaque centre suivant est tiré avec une probabilité
+/// proportionnelle au `cout` qui sépare un point de son centre le plus proche (la distance au carré
+/// pour le k-means classique). Les centres sont ainsi bien répartis dès le départ.
+fn initialiser_kmeans_pp<F: Fn(&[f32], &[f32]) -> f32>(
+    points: &[&[f32]],
+    k: usize,
+    rng: &mut StdRng,
+    cout: F,
+) -> Vec<Vec<f32>> {
+    let mut centres: Vec<Vec<f32>> = Vec::with_capacity(k);
+    if points.is_empty() || k == 0 {
+        return centres;
+    }
+
+    centres.push(points[rng.gen_range(0..points.len())].to_vec());
+    let mut couts: Vec<f32> = points.iter().map(|p| cout(p, &centres[0])).collect();
+
+    while centres.len() < k {
+        let total: f32 = couts.iter().sum();
+        let choisi = if total > 0.0 {
+            let mut tirage = rng.gen_range(0.0..total);
+            couts
+                .iter()
+                .position(|&c| {
+                    tirage -= c;
+                    tirage < 0.0
+                })
+                .unwrap_or(points.len() - 1)
+        } else {
+            // Tous les points coïncident avec un centre : tirage uniforme
+            rng.gen_range(0..points.len())
+        };
+
+        centres.push(points[choisi].to_vec());
+        let nouveau = centres.last().expect("un centre vient d'être ajouté");
+        for (c, p) in couts.iter_mut().zip(points) {
+            *c = c.min(cout(p, nouveau));
+        }
     }
+
+    centres
 }
 
+/// Calcule le carré de la distance euclidienne entre deux vecteurs, le coût minimisé par k-means.
+fn distance_carree(a: &[f32], b: &[f32]) -> f32 {
+    let d = distance_euclidienne(a, b);
+    d * d
+}
 
+/// Retourne l'indice du centre le plus proche d'un point et le carré de sa distance à ce centre.
+/// 
+/// Les égalités sont départagées en faveur du plus petit indice.
+fn centre_le_plus_proche(centres: &[Vec<f32>], p: &[f32]) -> (usize, f32) {
+    centres
+        .iter()
+        .map(|c| distance_carree(p, c))
+        .enumerate()
+        .fold((0, f32::INFINITY), |meilleur, courant| {
+            if courant.1 < meilleur.1 {
+                courant
+            } else {
+                meilleur
+            }
+        })
+}
 
-/// Fonction principale qui simule l'insertion de vecteurs et la recherche de similarités.
+/// Affine des centres par l'algorithme de Lloyd, à partir de centres initiaux donnés.
 /// 
-/// Dans cette fonction, plusieurs vecteurs sont insérés dans la base de données,
-/// un vecteur de requête est généré et les `n` vecteurs les plus similaires à ce vecteur sont
-/// recherchés et affichés.
+/// Chaque itération recalcule chaque centre comme la moyenne de son groupe (un groupe vide conserve
+/// son centre) puis réaffecte les points ; on s'arrête quand plus aucune affectation ne change, ou
+/// après `iters` itérations. Aucune des deux étapes ne peut augmenter l'inertie : celle du résultat
+/// est donc au plus celle des centres initiaux.
+/// 
+/// # Retour
+/// 
+/// Retourne le groupe de chaque point, les centres finaux et l'inertie (somme des distances au
+/// carré de chaque point à son centre).
+fn lloyd(points: &[&[f32]], mut centroides: Vec<Vec<f32>>, iters: usize) -> (Vec<usize>, Vec<Vec<f32>>, f32) {
+    let mut groupes: Vec<usize> = points.iter().map(|p| centre_le_plus_proche(&centroides, p).0).collect();
+
+    for _ in 0..iters {
+        // Recalculer chaque centre comme la moyenne de son groupe
+        for (indice, centre) in centroides.iter_mut().enumerate() {
+            let membres: Vec<&[f32]> = points
+                .iter()
+                .zip(&groupes)
+                .filter(|(_, &groupe)| groupe == indice)
+                .map(|(p, _)| *p)
+                .collect();
+            if !membres.is_empty() {
+                for (d, valeur) in centre.iter_mut().enumerate() {
+                    *valeur = membres.iter().map(|m| m[d]).sum::<f32>() / membres.len() as f32;
+                }
+            }
+        }
+
+        let nouveaux: Vec<usize> = points.iter().map(|p| centre_le_plus_proche(&centroides, p).0).collect();
+        if nouveaux == groupes {
+            break;
+        }
+        groupes = nouveaux;
+    }
+
+    let inertie = points.iter().map(|p| centre_le_plus_proche(&centroides, p).1).sum();
+    (groupes, centroides, inertie)
+}
+
+/// Base limitée à une fenêtre temporelle glissante : seules les entrées récentes sont conservées.
+/// 
+/// Les entrées sont horodatées à l'insertion par la `Db` sous-jacente. `purger` supprime celles qui
+/// sont sorties de la fenêtre ; la purge n'a lieu qu'à la demande, pour que l'appelant choisisse sa
+/// fréquence (à chaque insertion, ou périodiquement pour un coût amorti). Les recherches se font sur
+/// la base exposée par `db`.
+/// 
+/// # Champs
+/// 
+/// * `db` - La base sous-jacente.
+/// * `fenetre` - L'âge maximal des entrées conservées par `purger`.
 /// 
 /// # Exemple
 /// 
 /// ```
-/// main();
+/// let mut fenetre = FenetreDb::new(Duration::from_secs(30 * 24 * 3600));
+/// fenetre.insert(Uuid::new_v4(), embedding);
+/// fenetre.purger();
+/// let resultats = fenetre.db().trouver_les_plus_similaires(&query, 10);
 /// ```
-fn main() {
-    let mut db = Db::new();
-    let embedding_dimension = 768;
-    let phrase = "Ceci est un exemple de phrase";
+struct FenetreDb<S = HashMap<Uuid, Vec<f32>>> {
+    db: Db<S>,
+    fenetre: Duration,
+}
 
-    // Insérer un vecteur d'embarquement pour chaque mot dans la base de données
-    for _mot in phrase.split_whitespace() {
-        let uuid = Uuid::new_v4();
-        let embedding = generer_embedding(embedding_dimension);
-        db.insert(uuid, embedding);  // On insère chaque mot avec son vecteur d'embarquement
+impl FenetreDb {
+    /// Crée une base vide limitée à la fenêtre donnée.
+    fn new(fenetre: Duration) -> Self {
+        FenetreDb::avec_db(Db::new(), fenetre)
     }
+}
 
-    // Générer un vecteur de requête
-    let query_embedding = generer_embedding(embedding_dimension);
+impl<S: VectorStore> FenetreDb<S> {
+    /// Limite une base existante à la fenêtre donnée. Ses entrées gardent leur horodatage.
+    fn avec_db(db: Db<S>, fenetre: Duration) -> Self {
+        FenetreDb { db, fenetre }
+    }
 
-    // Trouver les 3 vecteurs les plus similaires
-    let plus_similaire = db.trouver_les_plus_similaires(&query_embedding, 3);
+    /// Retourne la base sous-jacente, pour les recherches.
+    fn db(&self) -> &Db<S> {
+        &self.db
+    }
 
-    println!("Les 3 vecteurs les plus similaires :");
-    for (uuid, similarity) in plus_similaire {
-        println!("UUID: {}, Similarité: {:.4}", uuid, similarity);
+    /// Insère un vecteur horodaté à l'instant présent.
+    fn insert(&mut self, uuid: Uuid, embedding: Vec<f32>) {
+        self.db.insert(uuid, embedding);
+    }
+
+    /// Insère un vecteur avec une date explicite (voir `Db::insert_avec_horodatage`).
+    fn insert_avec_horodatage(&mut self, uuid: Uuid, embedding: Vec<f32>, horodatage: SystemTime) {
+        self.db.insert_avec_horodatage(uuid, embedding, horodatage);
+    }
+
+    /// Supprime toutes les entrées insérées strictement avant une date.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `cutoff` - La date limite : les entrées de date antérieure sont supprimées, celles de date
+    ///   égale ou postérieure sont conservées.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre d'entrées supprimées.
+    fn purger_avant(&mut self, cutoff: SystemTime) -> usize {
+        let perimees: Vec<Uuid> = self
+            .db
+            .horodatages
+            .iter()
+            .filter(|(_, &horodatage)| horodatage < cutoff)
+            .map(|(uuid, _)| *uuid)
+            .collect();
+        self.db.remove_many(perimees)
+    }
+
+    /// Supprime les entrées plus anciennes que la fenêtre, par rapport à l'instant présent.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre d'entrées supprimées.
+    fn purger(&mut self) -> usize {
+        match SystemTime::now().checked_sub(self.fenetre) {
+            Some(cutoff) => self.purger_avant(cutoff),
+            None => 0, // Fenêtre plus longue que l'époque représentable : rien n'est périmé
+        }
     }
 }
 
+/// Résout le problème d'affectation par l'algorithme hongrois (méthode des potentiels).
+/// 
+/// # Arguments
+/// 
+/// * `couts` - La matrice des coûts, `lignes × colonnes`, avec au plus autant de lignes que de colonnes.
+/// 
+/// # Retour
+/// 
+/// Retourne, pour chaque ligne, l'indice de la colonne qui lui est affectée, de sorte que la somme
+/// des coûts soit minimale. Chaque colonne est affectée à au plus une ligne.
+fn affectation_hongroise(couts: &[Vec<f64>]) -> Vec<usize> {
+    let lignes = couts.len();
+    let colonnes = couts.first().map_or(0, Vec::len);
+    assert!(lignes <= colonnes, "il faut au plus autant de lignes que de colonnes");
+
+    // Indices décalés de un : la ligne et la colonne 0 sont fictives
+    let mut u = vec![0.0f64; lignes + 1];
+    let mut v = vec![0.0f64; colonnes + 1];
+    let mut ligne_de = vec![0usize; colonnes + 1];
+    let mut precedente = vec![0usize; colonnes + 1];
+
+    for i in 1..=lignes {
+        ligne_de[0] = i;
+        let mut j0 = 0;
+        let mut minimums = vec![f64::INFINITY; colonnes + 1];
+        let mut visitees = vec![false; colonnes + 1];
+
+        // Chercher un chemin augmentant depuis la ligne i en ajustant les potentiels
+        loop {
+            visitees[j0] = true;
+            let i0 = ligne_de[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=colonnes {
+                if visitees[j] {
+                    continue;
+                }
+                let reduit = couts[i0 - 1][j - 1] - u[i0] - v[j];
+                if reduit < minimums[j] {
+                    minimums[j] = reduit;
+                    precedente[j] = j0;
+                }
+                if minimums[j] < delta {
+                    delta = minimums[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=colonnes {
+                if visitees[j] {
+                    u[ligne_de[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minimums[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if ligne_de[j0] == 0 {
+                break;
+            }
+        }
+
+        // Inverser les affectations le long du chemin trouvé
+        while j0 != 0 {
+            let j1 = precedente[j0];
+            ligne_de[j0] = ligne_de[j1];
+            j0 = j1;
+        }
+    }
+
+    let mut affectation = vec![0usize; lignes];
+    for j in 1..=colonnes {
+        if ligne_de[j] != 0 {
+            affectation[ligne_de[j] - 1] = j - 1;
+        }
+    }
+    affectation
+}
+
+/// Inverse une matrice carrée par élimination de Gauss-Jordan avec pivot partiel.
+/// 
+/// # Retour
+/// 
+/// Retourne l'inverse, ou `None` si la matrice est (numériquement) singulière.
+fn inverser_matrice(matrice: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let taille = matrice.len();
+    let mut gauche: Vec<Vec<f64>> = matrice.to_vec();
+    let mut inverse: Vec<Vec<f64>> = (0..taille)
+        .map(|i| (0..taille).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for colonne in 0..taille {
+        // Choisir la ligne de plus grand pivot pour limiter les erreurs d'arrondi
+        let pivot = (colonne..taille).max_by(|&a, &b| {
+            gauche[a][colonne]
+                .abs()
+                .partial_cmp(&gauche[b][colonne].abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })?;
+        if gauche[pivot][colonne].abs() < 1e-300 {
+            return None;
+        }
+        gauche.swap(colonne, pivot);
+        inverse.swap(colonne, pivot);
+
+        let facteur = gauche[colonne][colonne];
+        for j in 0..taille {
+            gauche[colonne][j] /= facteur;
+            inverse[colonne][j] /= facteur;
+        }
+        for ligne in 0..taille {
+            if ligne == colonne {
+                continue;
+            }
+            let coefficient = gauche[ligne][colonne];
+            if coefficient == 0.0 {
+                continue;
+            }
+            for j in 0..taille {
+                gauche[ligne][j] -= coefficient * gauche[colonne][j];
+                inverse[ligne][j] -= coefficient * inverse[colonne][j];
+            }
+        }
+    }
+    Some(inverse)
+}
+
+/// Résultat de recherche muni de l'ordre de `trier_par_score`, pour pouvoir le placer dans un tas.
+/// 
+/// Un candidat est « plus grand » qu'un autre s'il serait classé avant lui : score plus élevé, ou
+/// score égal et UUID plus petit.
+#[derive(Debug, Clone, Copy)]
+struct Candidat {
+    score: f32,
+    uuid: Uuid,
+}
+
+impl Ord for Candidat {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .partial_cmp(&other.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(other.uuid.cmp(&self.uuid))
+    }
+}
+
+impl PartialOrd for Candidat {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Candidat {}
+
+/// Fusionne les résultats de plusieurs bases (shards) en un top-n global.
+/// 
+/// Chaque liste partielle doit déjà être triée comme le sont les résultats de
+/// `trouver_les_plus_similaires` (similarité décroissante, puis UUID croissant). La fusion se fait
+/// par un tas à `k` entrées (une par shard) : seuls les éléments effectivement retournés sont
+/// parcourus, sans concaténer ni retrier l'ensemble des résultats. Le résultat est identique à un tri
+/// de l'union suivi des `n` premiers éléments, départage des égalités compris.
+/// 
+/// # Arguments
+/// 
+/// * `partials` - Les résultats triés de chaque shard.
+/// * `n` - Le nombre de résultats à retourner.
+/// 
+/// # Retour
+/// 
+/// Retourne les `n` meilleurs tuples `(Uuid, f32)` de l'ensemble des shards.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let partiels = vec![shard_a.trouver_les_plus_similaires(&query, 10), shard_b.trouver_les_plus_similaires(&query, 10)];
+/// let top = merge_top_n(&partiels, 10);
+/// ```
+fn merge_top_n(partials: &[Vec<(Uuid, f32)>], n: usize) -> Vec<(Uuid, f32)> {
+    let mut tas: BinaryHeap<(Candidat, usize, usize)> = partials
+        .iter()
+        .enumerate()
+        .filter_map(|(shard, resultats)| {
+            resultats
+                .first()
+                .map(|&(uuid, score)| (Candidat { score, uuid }, shard, 0))
+        })
+        .collect();
+
+    let mut fusion = Vec::with_capacity(n.min(partials.iter().map(Vec::len).sum()));
+    while fusion.len() < n {
+        let Some((candidat, shard, position)) = tas.pop() else {
+            break;
+        };
+        fusion.push((candidat.uuid, candidat.score));
+
+        // Avancer dans le shard dont provient le candidat
+        if let Some(&(uuid, score)) = partials[shard].get(position + 1) {
+            tas.push((Candidat { score, uuid }, shard, position + 1));
+        }
+    }
+
+    fusion
+}
+
+/// Réunit les résultats de plusieurs requêtes en un top-n global sans doublons.
+/// 
+/// Contrairement à `merge_top_n`, prévu pour des shards disjoints, un même UUID peut apparaître
+/// dans plusieurs listes : il n'est alors retenu qu'une fois, avec le meilleur de ses scores. Les
+/// listes n'ont pas besoin d'être triées. Seule une entrée par UUID distinct est conservée pendant
+/// la fusion.
+/// 
+/// # Arguments
+/// 
+/// * `results` - Les résultats de chaque requête.
+/// * `n` - Le nombre de résultats à retourner.
+/// 
+/// # Retour
+/// 
+/// Retourne les `n` meilleurs tuples `(Uuid, f32)` de l'union, triés comme les résultats de
+/// `trouver_les_plus_similaires`.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// // Recherche multi-requêtes : plusieurs reformulations d'une même question
+/// let resultats: Vec<Vec<(Uuid, f32)>> = reformulations.iter().map(|q| db.trouver_les_plus_similaires(q, 20)).collect();
+/// let top = fusionner_resultats(&resultats, 10);
+/// ```
+fn fusionner_resultats(results: &[Vec<(Uuid, f32)>], n: usize) -> Vec<(Uuid, f32)> {
+    let mut meilleurs: HashMap<Uuid, f32> = HashMap::new();
+    for &(uuid, score) in results.iter().flatten() {
+        meilleurs
+            .entry(uuid)
+            .and_modify(|meilleur| *meilleur = meilleur.max(score))
+            .or_insert(score);
+    }
+
+    let mut fusion: Vec<(Uuid, f32)> = meilleurs.into_iter().collect();
+    trier_par_score(&mut fusion);
+    fusion.truncate(n);
+    fusion
+}
+
+/// Fusionne plusieurs classements par la méthode Reciprocal Rank Fusion (RRF).
+/// 
+/// Chaque UUID reçoit le score `Σ 1 / (k + rang)` sur les classements où il figure, le rang
+/// commençant à 1. Seuls les rangs interviennent, pas les scores : des classements issus de mesures
+/// ou de modèles aux échelles incomparables se combinent donc sans normalisation. Un UUID bien classé
+/// dans plusieurs listes l'emporte sur un UUID premier d'une seule liste. Si un UUID apparaît
+/// plusieurs fois dans un même classement, seule sa meilleure position compte.
+/// 
+/// # Arguments
+/// 
+/// * `rankings` - Les classements, du meilleur au moins bon.
+/// * `k` - La constante de lissage (60 est la valeur usuelle) : plus elle est grande, moins les
+///   premiers rangs dominent.
+/// * `top_n` - Le nombre de résultats à retourner.
+/// 
+/// # Retour
+/// 
+/// Retourne les `top_n` tuples `(Uuid, score RRF)` triés par score décroissant, puis par UUID
+/// croissant.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let cosinus: Vec<Uuid> = db.trouver_les_plus_similaires(&query, 50).into_iter().map(|(uuid, _)| uuid).collect();
+/// let produit: Vec<Uuid> = db.trouver_par_produit_scalaire(&query, 50).into_iter().map(|(uuid, _)| uuid).collect();
+/// let top = rrf(&[cosinus, produit], 60, 10);
+/// ```
+fn rrf(rankings: &[Vec<Uuid>], k: usize, top_n: usize) -> Vec<(Uuid, f32)> {
+    let mut scores: HashMap<Uuid, f32> = HashMap::new();
+    for classement in rankings {
+        let mut vus = HashSet::new();
+        for (position, uuid) in classement.iter().enumerate() {
+            if vus.insert(*uuid) {
+                *scores.entry(*uuid).or_insert(0.0) += 1.0 / (k + position + 1) as f32;
+            }
+        }
+    }
+
+    let mut fusion: Vec<(Uuid, f32)> = scores.into_iter().collect();
+    trier_par_score(&mut fusion);
+    fusion.truncate(top_n);
+    fusion
+}
+
+/// Rééchelonne les scores de chaque liste de résultats d'un lot de requêtes, pour les rendre
+/// comparables d'une requête à l'autre.
+/// 
+/// Chaque liste est normalisée indépendamment, selon sa propre distribution de scores : une
+/// requête dont tous les résultats ont des scores élevés ne domine plus un classement global
+/// construit à partir de plusieurs requêtes. La transformation est croissante, si bien que l'ordre
+/// à l'intérieur de chaque liste est conservé.
+/// 
+/// # Arguments
+/// 
+/// * `results` - Les résultats de chaque requête, modifiés en place. Les listes vides sont laissées
+///   telles quelles.
+/// * `mode` - La normalisation appliquée (voir `NormalisationScores`).
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let mut lots: Vec<Vec<(Uuid, f32)>> = requetes.iter().map(|q| db.trouver_les_plus_similaires(q, 10)).collect();
+/// normaliser_scores_batch(&mut lots, NormalisationScores::MinMax);
+/// let mut flux: Vec<(Uuid, f32)> = lots.into_iter().flatten().collect();
+/// trier_par_score(&mut flux);
+/// ```
+fn normaliser_scores_batch(results: &mut [Vec<(Uuid, f32)>], mode: NormalisationScores) {
+    for resultats in results.iter_mut().filter(|resultats| !resultats.is_empty()) {
+        match mode {
+            NormalisationScores::MinMax => {
+                let (min, max) = resultats
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &(_, score)| {
+                        (min.min(score), max.max(score))
+                    });
+                for (_, score) in resultats.iter_mut() {
+                    // Des scores tous égaux sont tous considérés comme les meilleurs
+                    *score = if max > min { (*score - min) / (max - min) } else { 1.0 };
+                }
+            }
+            NormalisationScores::ZScore => {
+                let nombre = resultats.len() as f32;
+                let moyenne = resultats.iter().map(|(_, score)| score).sum::<f32>() / nombre;
+                let variance = resultats
+                    .iter()
+                    .map(|(_, score)| (score - moyenne) * (score - moyenne))
+                    .sum::<f32>()
+                    / nombre;
+                let ecart_type = variance.sqrt();
+                for (_, score) in resultats.iter_mut() {
+                    *score = if ecart_type > 0.0 { (*score - moyenne) / ecart_type } else { 0.0 };
+                }
+            }
+        }
+    }
+}
+
+/// Manière de ramener un vecteur à une dimension cible (voir `Db::conformer_dimension`).
+/// 
+/// * `Pad` - Les vecteurs plus courts sont complétés par des zéros.
+/// * `Truncate` - Les vecteurs plus longs sont tronqués à leurs premières composantes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConformMode {
+    Pad,
+    Truncate,
+}
+
+/// Règle utilisée pour départager des résultats de recherche ayant la même similarité.
+/// 
+/// * `Uuid` - Les résultats à égalité sont ordonnés par UUID croissant.
+/// * `InsertionOrder` - Les résultats à égalité sont ordonnés par ordre d'insertion, du plus récent
+///   au plus ancien : la dernière entrée insérée l'emporte. Ce mode repose sur le compteur
+///   d'insertion tenu par `Db` : toute sérialisation de la base doit conserver ce compteur (ou
+///   l'ordre des entrées) pour que l'ordre survive à un rechargement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TieBreak {
+    Uuid,
+    InsertionOrder,
+}
+
+/// Normalisation appliquée aux scores de chaque requête par `normaliser_scores_batch`.
+/// 
+/// * `MinMax` - Les scores sont ramenés dans `[0, 1]` : le meilleur vaut `1.0`, le moins bon `0.0`
+///   (tous valent `1.0` s'ils sont égaux).
+/// * `ZScore` - Les scores sont centrés sur leur moyenne et divisés par leur écart type (de
+///   population) ; ils valent tous `0.0` s'ils sont égaux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NormalisationScores {
+    MinMax,
+    ZScore,
+}
+
+/// Trie des résultats par similarité décroissante, les égalités étant départagées par UUID croissant.
+/// 
+/// C'est l'ordre de référence de toutes les recherches de la base : il garantit un résultat
+/// identique quel que soit l'ordre d'itération du `HashMap`.
+fn trier_par_score(resultats: &mut [(Uuid, f32)]) {
+    resultats.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+}
+
+/// Erreur pouvant survenir lors de l'export de la base au format Parquet.
+/// 
+/// * `Io` - Le fichier de destination n'a pas pu être créé.
+/// * `Arrow` - La table Arrow n'a pas pu être construite.
+/// * `Parquet` - L'écriture Parquet a échoué.
+/// * `DimensionIncoherente` - Les vecteurs stockés n'ont pas tous la même dimension.
+#[cfg(feature = "parquet")]
+#[derive(Debug)]
+enum ExportError {
+    Io(std::io::Error),
+    Arrow(arrow::error::ArrowError),
+    Parquet(parquet::errors::ParquetError),
+    DimensionIncoherente { attendue: usize, trouvee: usize },
+}
+
+#[cfg(feature = "parquet")]
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Io(e) => write!(f, "erreur d'entrée/sortie : {}", e),
+            ExportError::Arrow(e) => write!(f, "erreur Arrow : {}", e),
+            ExportError::Parquet(e) => write!(f, "erreur Parquet : {}", e),
+            ExportError::DimensionIncoherente { attendue, trouvee } => write!(
+                f,
+                "dimension incohérente : {} attendue, {} trouvée",
+                attendue, trouvee
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl std::error::Error for ExportError {}
+
+#[cfg(feature = "parquet")]
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<arrow::error::ArrowError> for ExportError {
+    fn from(e: arrow::error::ArrowError) -> Self {
+        ExportError::Arrow(e)
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for ExportError {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        ExportError::Parquet(e)
+    }
+}
+
+/// Erreur pouvant survenir lors d'une recherche faillible (`Db::try_search`).
+/// 
+/// * `EmptyDatabase` - La base ne contient aucun vecteur : aucune recherche n'a de sens, ce qui
+///   est distinct d'une recherche valide dont aucun résultat ne passe le seuil.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchError {
+    EmptyDatabase,
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::EmptyDatabase => write!(f, "la base de données est vide"),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+/// Différence entre deux états d'une base, calculée par `Db::diff`.
+/// 
+/// # Champs
+/// 
+/// * `ajoutes` - Les UUIDs présents uniquement dans le nouvel état.
+/// * `supprimes` - Les UUIDs présents uniquement dans l'ancien état.
+/// * `modifies` - Les UUIDs présents des deux côtés mais dont le vecteur a changé.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct DbDiff {
+    ajoutes: HashSet<Uuid>,
+    supprimes: HashSet<Uuid>,
+    modifies: HashSet<Uuid>,
+}
+
+/// Table des normes des vecteurs d'une base, construite par `Db::norm_cache`.
+/// 
+/// Elle permet d'écrire des boucles de recherche sur mesure sans recalculer la norme de chaque
+/// vecteur stocké à chaque requête (voir `Db::cosine_with_cache`).
+/// 
+/// La table est une photographie de la base au moment de sa construction : elle n'est pas mise à
+/// jour automatiquement. Elle devient invalide dès que la base est modifiée, c'est-à-dire dès qu'un
+/// vecteur est inséré, remplacé ou supprimé ; il faut alors la reconstruire.
+/// 
+/// # Champs
+/// 
+/// * `normes` - La norme euclidienne de chaque vecteur, indexée par UUID.
+#[derive(Debug, Clone, Default)]
+struct NormCache {
+    normes: HashMap<Uuid, f32>,
+}
+
+/// Métadonnées associées à une entrée de la base, sous forme de paires clé/valeur.
+type Metadonnees = HashMap<String, String>;
+
+/// Échantillon uniforme d'un flux d'insertions, tenu par l'algorithme R de Vitter.
+/// 
+/// # Champs
+/// 
+/// * `capacite` - Le nombre maximal d'éléments conservés.
+/// * `vus` - Le nombre d'éléments observés depuis la création.
+/// * `echantillon` - Les éléments actuellement retenus.
+/// * `rng` - Le générateur utilisé pour les tirages.
+struct Reservoir {
+    capacite: usize,
+    vus: u64,
+    echantillon: Vec<(Uuid, Vec<f32>)>,
+    rng: StdRng,
+}
+
+impl Reservoir {
+    /// Présente un nouvel élément au réservoir.
+    /// 
+    /// Le `k`-ième élément observé remplace un élément de l'échantillon avec une probabilité
+    /// `capacite / k`, ce qui garantit que chaque élément vu a la même probabilité d'être retenu,
+    /// quel que soit le nombre total d'éléments.
+    fn observer(&mut self, uuid: Uuid, embedding: &[f32]) {
+        self.vus += 1;
+        if self.echantillon.len() < self.capacite {
+            self.echantillon.push((uuid, embedding.to_vec()));
+        } else {
+            let position = self.rng.gen_range(0..self.vus);
+            if position < self.capacite as u64 {
+                self.echantillon[position as usize] = (uuid, embedding.to_vec());
+            }
+        }
+    }
+}
+
+/// Signature placée en tête des fichiers écrits par `Db::save`.
+const MAGIC: &[u8; 4] = b"TPDB";
+
+/// Version du format de fichier de `Db::save`.
+const VERSION_FORMAT: u8 = 1;
+
+/// Lit exactement `N` octets.
+fn lire_octets<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut octets = [0u8; N];
+    r.read_exact(&mut octets)?;
+    Ok(octets)
+}
+
+/// Écrit une chaîne précédée de sa longueur en octets (`u32` petit-boutiste).
+fn ecrire_chaine<W: Write>(w: &mut W, chaine: &str) -> io::Result<()> {
+    w.write_all(&(chaine.len() as u32).to_le_bytes())?;
+    w.write_all(chaine.as_bytes())
+}
+
+/// Lit une chaîne écrite par `ecrire_chaine`.
+fn lire_chaine<R: Read>(r: &mut R) -> io::Result<String> {
+    let longueur = u32::from_le_bytes(lire_octets(r)?) as usize;
+    let mut octets = Vec::new();
+    r.take(longueur as u64).read_to_end(&mut octets)?;
+    if octets.len() != longueur {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "chaîne tronquée"));
+    }
+    String::from_utf8(octets).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Copie des vecteurs d'une base empilés en une matrice, construite par `Db::index_matriciel`.
+/// 
+/// Les vecteurs sont rangés ligne par ligne (un vecteur par ligne, triés par UUID) dans un seul
+/// tableau contigu, ce qui permet de calculer tous les produits scalaires avec la requête en un seul
+/// produit matrice-vecteur. Avec la fonctionnalité `blas`, ce produit est délégué à la BLAS du
+/// système (`sgemv`), nettement plus rapide que la boucle scalaire pour les grandes dimensions ;
+/// sans elle, la boucle scalaire est utilisée et les scores sont identiques bit à bit à ceux de
+/// `Db::trouver_les_plus_similaires`. Avec la BLAS, l'ordre des additions diffère et les scores
+/// peuvent varier au dernier chiffre près.
+/// 
+/// Comme `NormCache`, l'index est une photographie de la base : il faut le reconstruire après toute
+/// modification de celle-ci.
+/// 
+/// # Champs
+/// 
+/// * `uuids` - L'UUID de chaque ligne.
+/// * `vecteurs` - Les composantes des vecteurs, ligne par ligne.
+/// * `dimension` - La dimension commune des vecteurs (le nombre de colonnes).
+/// * `normes` - La norme euclidienne de chaque ligne.
+#[derive(Debug, Clone)]
+struct IndexMatriciel {
+    uuids: Vec<Uuid>,
+    vecteurs: Vec<f32>,
+    dimension: usize,
+    normes: Vec<f32>,
+}
+
+impl IndexMatriciel {
+    /// Calcule le produit scalaire de la requête avec chaque ligne de la matrice.
+    fn produits_scalaires(&self, query: &[f32]) -> Vec<f32> {
+        assert_eq!(query.len(), self.dimension, "la requête doit avoir la dimension de l'index");
+
+        #[cfg(feature = "blas")]
+        {
+            let matrice = ndarray::ArrayView2::from_shape((self.uuids.len(), self.dimension), &self.vecteurs)
+                .expect("la matrice a la taille uuids × dimension");
+            matrice.dot(&ndarray::ArrayView1::from(query)).to_vec()
+        }
+
+        #[cfg(not(feature = "blas"))]
+        {
+            if self.dimension == 0 {
+                return vec![0.0; self.uuids.len()];
+            }
+            self.vecteurs
+                .chunks_exact(self.dimension)
+                .map(|ligne| produit_scalaire(query, ligne))
+                .collect()
+        }
+    }
+
+    /// Trouve les `n` vecteurs de l'index les plus similaires à la requête (similarité cosinus).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête, de la dimension de l'index.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, f32)`, triés comme ceux de
+    /// `Db::trouver_les_plus_similaires`.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si la requête n'a pas la dimension de l'index.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let index = db.index_matriciel();
+    /// let resultats = index.trouver_les_plus_similaires(&query, 10);
+    /// ```
+    fn trouver_les_plus_similaires(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        let norme_query = norme(query);
+        let mut scores: Vec<(Uuid, f32)> = self
+            .uuids
+            .iter()
+            .zip(self.produits_scalaires(query))
+            .zip(&self.normes)
+            .map(|((uuid, produit), &norme_ligne)| {
+                if norme_query == 0.0 || norme_ligne == 0.0 {
+                    (*uuid, 0.0) // Éviter la division par zéro
+                } else {
+                    (*uuid, produit / (norme_query * norme_ligne))
+                }
+            })
+            .collect();
+
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        scores
+    }
+}
+
+/// Copie des vecteurs d'une base rangés par colonnes, construite par `Db::index_colonnes`.
+/// 
+/// Toutes les premières composantes sont contiguës, puis toutes les deuxièmes, etc. La recherche
+/// parcourt la requête dimension par dimension et ajoute `q[d] · x[d]` au score de chaque candidat :
+/// la boucle interne lit une colonne contiguë et écrit un tampon de scores contigu, ce que le
+/// compilateur vectorise bien pour de grands lots. Les produits sont accumulés dans le même ordre
+/// que dans `calcul_similarite`, si bien que les scores sont identiques bit à bit à ceux de
+/// `Db::trouver_les_plus_similaires`.
+/// 
+/// Comme `IndexMatriciel`, l'index est une photographie de la base : il faut le reconstruire après
+/// toute modification de celle-ci.
+/// 
+/// # Champs
+/// 
+/// * `uuids` - L'UUID de chaque candidat, triés.
+/// * `colonnes` - Les composantes, colonne par colonne : `colonnes[d * uuids.len() + i]` est la
+///   composante `d` du candidat `i`.
+/// * `dimension` - La dimension commune des vecteurs.
+/// * `normes` - La norme euclidienne de chaque candidat.
+#[derive(Debug, Clone)]
+struct IndexColonnes {
+    uuids: Vec<Uuid>,
+    colonnes: Vec<f32>,
+    dimension: usize,
+    normes: Vec<f32>,
+}
+
+impl IndexColonnes {
+    /// Calcule le produit scalaire de la requête avec chaque candidat, colonne par colonne.
+    fn produits_scalaires(&self, query: &[f32]) -> Vec<f32> {
+        assert_eq!(query.len(), self.dimension, "la requête doit avoir la dimension de l'index");
+
+        let mut scores = vec![0.0f32; self.uuids.len()];
+        if scores.is_empty() {
+            return scores;
+        }
+        for (&q, colonne) in query.iter().zip(self.colonnes.chunks_exact(self.uuids.len())) {
+            for (score, &x) in scores.iter_mut().zip(colonne) {
+                *score += q * x;
+            }
+        }
+        scores
+    }
+
+    /// Trouve les `n` vecteurs de l'index les plus similaires à la requête (similarité cosinus).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête, de la dimension de l'index.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les mêmes résultats que `Db::trouver_les_plus_similaires`.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si la requête n'a pas la dimension de l'index.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let index = db.index_colonnes();
+    /// let resultats = index.trouver_les_plus_similaires(&query, 10);
+    /// ```
+    fn trouver_les_plus_similaires(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        let norme_query = norme(query);
+        let mut scores: Vec<(Uuid, f32)> = self
+            .uuids
+            .iter()
+            .zip(self.produits_scalaires(query))
+            .zip(&self.normes)
+            .map(|((uuid, produit), &norme_candidat)| {
+                if norme_query == 0.0 || norme_candidat == 0.0 {
+                    (*uuid, 0.0) // Éviter la division par zéro
+                } else {
+                    (*uuid, produit / (norme_query * norme_candidat))
+                }
+            })
+            .collect();
+
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        scores
+    }
+}
+
+/// Index capable de retourner les `k` plus proches voisins d'une requête, exactement ou de façon
+/// approchée.
+/// 
+/// Ce trait permet de comparer un index approché (ANN) à la recherche exacte de `Db`, par exemple
+/// avec `MoniteurRappel`.
+trait RechercheApprochee {
+    /// Retourne les `k` vecteurs jugés les plus proches de la requête, du plus proche au moins proche.
+    fn rechercher(&self, query: &[f32], k: usize) -> Vec<(Uuid, f32)>;
+}
+
+impl<S: VectorStore> RechercheApprochee for Db<S> {
+    fn rechercher(&self, query: &[f32], k: usize) -> Vec<(Uuid, f32)> {
+        self.trouver_sans_compter(query, k, TieBreak::Uuid)
+    }
+}
+
+impl RechercheApprochee for IndexMatriciel {
+    fn rechercher(&self, query: &[f32], k: usize) -> Vec<(Uuid, f32)> {
+        self.trouver_les_plus_similaires(query, k)
+    }
+}
+
+impl RechercheApprochee for IndexColonnes {
+    fn rechercher(&self, query: &[f32], k: usize) -> Vec<(Uuid, f32)> {
+        self.trouver_les_plus_similaires(query, k)
+    }
+}
+
+/// Index approché dont la largeur de recherche est réglable, comme le paramètre `ef` d'un HNSW.
+/// 
+/// Un `ef` plus grand explore plus de candidats : le rappel augmente, la latence aussi.
+trait RechercheParametree {
+    /// Retourne les `n` vecteurs jugés les plus proches de la requête en explorant `ef` candidats.
+    fn rechercher_ef(&self, query: &[f32], n: usize, ef: usize) -> Vec<(Uuid, f32)>;
+}
+
+/// Résultat d'une recherche à `ef` adaptatif (voir `recherche_ef_adaptative`).
+/// 
+/// # Champs
+/// 
+/// * `resultats` - Les résultats de la dernière recherche effectuée.
+/// * `ef` - La largeur de recherche qui les a produits, à surveiller : une valeur qui approche
+///   `ef_max` pour beaucoup de requêtes signale un index difficile à parcourir.
+#[derive(Debug, Clone, PartialEq)]
+struct ResultatEfAdaptatif {
+    resultats: Vec<(Uuid, f32)>,
+    ef: usize,
+}
+
+/// Recherche dans un index approché en élargissant `ef` jusqu'à ce que les résultats se stabilisent.
+/// 
+/// La recherche est lancée avec `ef_initial`, puis `ef` est doublé à chaque tour. Dès que deux tours
+/// consécutifs retournent le même ensemble de `n` UUIDs, l'élargissement n'apporte plus rien et la
+/// recherche s'arrête : une requête facile se contente d'un petit `ef`, une requête difficile en
+/// obtient un plus grand, sans réglage manuel. `ef` ne dépasse jamais `ef_max`.
+/// 
+/// # Arguments
+/// 
+/// * `index` - L'index approché interrogé.
+/// * `query` - Le vecteur de requête.
+/// * `n` - Le nombre de résultats à retourner.
+/// * `ef_initial` - La largeur du premier tour (au moins `1`).
+/// * `ef_max` - La largeur maximale autorisée.
+/// 
+/// # Retour
+/// 
+/// Retourne les résultats du dernier tour et la largeur `ef` qui les a produits.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let adaptatif = recherche_ef_adaptative(&hnsw, &query, 10, 16, 1024);
+/// println!("ef final : {}", adaptatif.ef);
+/// ```
+fn recherche_ef_adaptative<I: RechercheParametree>(
+    index: &I,
+    query: &[f32],
+    n: usize,
+    ef_initial: usize,
+    ef_max: usize,
+) -> ResultatEfAdaptatif {
+    let ensemble = |resultats: &[(Uuid, f32)]| -> HashSet<Uuid> {
+        resultats.iter().map(|(uuid, _)| *uuid).collect()
+    };
+
+    let mut ef = ef_initial.max(1).min(ef_max.max(1));
+    let mut resultats = index.rechercher_ef(query, n, ef);
+    while ef < ef_max {
+        let suivant = (ef * 2).min(ef_max);
+        let nouveaux = index.rechercher_ef(query, n, suivant);
+        let stable = ensemble(&nouveaux) == ensemble(&resultats);
+        ef = suivant;
+        resultats = nouveaux;
+        if stable {
+            break;
+        }
+    }
+
+    ResultatEfAdaptatif { resultats, ef }
+}
+
+/// Suivi continu du rappel@k d'un index approché par rapport à la recherche exacte.
+/// 
+/// Seule une fraction des requêtes présentées est évaluée (tirée au sort), ce qui permet de brancher
+/// le moniteur sur le trafic réel sans doubler le coût de chaque recherche. Le rappel est moyenné sur
+/// les `fenetre` dernières requêtes évaluées, de sorte qu'une régression récente n'est pas noyée dans
+/// l'historique.
+/// 
+/// # Champs
+/// 
+/// * `k` - Le nombre de voisins comparés.
+/// * `fraction` - La probabilité qu'une requête présentée soit évaluée.
+/// * `fenetre` - Le nombre maximal de mesures conservées.
+/// * `rappels` - Les dernières mesures de rappel, de la plus ancienne à la plus récente.
+/// * `rng` - Le générateur utilisé pour l'échantillonnage.
+struct MoniteurRappel {
+    k: usize,
+    fraction: f64,
+    fenetre: usize,
+    rappels: VecDeque<f32>,
+    rng: StdRng,
+}
+
+impl MoniteurRappel {
+    /// Crée un moniteur.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de voisins comparés (rappel@k).
+    /// * `fraction` - La fraction des requêtes à évaluer, dans `[0, 1]`.
+    /// * `fenetre` - Le nombre de mesures sur lesquelles le rappel est moyenné.
+    /// * `seed` - La graine du générateur, pour un échantillonnage reproductible.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `fraction` n'est pas dans `[0, 1]` ou si `fenetre` vaut zéro.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Évaluer 1 % du trafic, en moyennant le rappel@10 sur les 500 dernières mesures
+    /// let mut moniteur = MoniteurRappel::new(10, 0.01, 500, 42);
+    /// ```
+    fn new(k: usize, fraction: f64, fenetre: usize, seed: u64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&fraction),
+            "la fraction échantillonnée doit être dans [0, 1]"
+        );
+        assert!(fenetre > 0, "la fenêtre doit contenir au moins une mesure");
+        MoniteurRappel {
+            k,
+            fraction,
+            fenetre,
+            rappels: VecDeque::with_capacity(fenetre),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Présente une requête au moniteur, qui l'évalue avec la probabilité `fraction`.
+    /// 
+    /// Une requête évaluée est exécutée sur les deux index ; son rappel est la proportion des `k`
+    /// voisins exacts retrouvés par l'index approché.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `approche` - L'index approché à surveiller.
+    /// * `exacte` - La référence exacte, typiquement la `Db` elle-même.
+    /// * `query` - La requête issue du trafic.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le rappel de cette requête si elle a été évaluée, sinon `None`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// moniteur.observer(&index_ann, &db, &query);
+    /// if moniteur.recall_courant() < 0.9 {
+    ///     eprintln!("rappel dégradé");
+    /// }
+    /// ```
+    fn observer<A: RechercheApprochee, E: RechercheApprochee>(
+        &mut self,
+        approche: &A,
+        exacte: &E,
+        query: &[f32],
+    ) -> Option<f32> {
+        if !self.rng.gen_bool(self.fraction) {
+            return None;
+        }
+
+        let attendus: HashSet<Uuid> = exacte
+            .rechercher(query, self.k)
+            .into_iter()
+            .map(|(uuid, _)| uuid)
+            .collect();
+        if attendus.is_empty() {
+            return None;
+        }
+        let retrouves = approche
+            .rechercher(query, self.k)
+            .iter()
+            .filter(|(uuid, _)| attendus.contains(uuid))
+            .count();
+        let rappel = retrouves as f32 / attendus.len() as f32;
+
+        if self.rappels.len() == self.fenetre {
+            self.rappels.pop_front();
+        }
+        self.rappels.push_back(rappel);
+        Some(rappel)
+    }
+
+    /// Retourne le rappel@k moyen sur la fenêtre courante.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne une valeur dans `[0, 1]`, ou `0.0` tant qu'aucune requête n'a été évaluée.
+    fn recall_courant(&self) -> f32 {
+        if self.rappels.is_empty() {
+            return 0.0;
+        }
+        self.rappels.iter().sum::<f32>() / self.rappels.len() as f32
+    }
+}
+
+/// Estimation, par HyperLogLog, du nombre d'UUIDs distincts apparus dans les résultats d'un flux de
+/// requêtes.
+/// 
+/// Chaque UUID est haché ; les premiers bits du hachage désignent un registre, qui retient le plus
+/// grand rang du premier bit à 1 parmi les bits restants. La mémoire est fixe (`2^precision`
+/// octets), quel que soit le nombre de requêtes observées, et l'erreur relative type de
+/// l'estimation vaut `1,04 / √(2^precision)` (environ 1,6 % pour une précision de 12).
+/// 
+/// # Champs
+/// 
+/// * `n` - Le nombre de résultats retenus par requête.
+/// * `precision` - Le nombre de bits du hachage qui désignent un registre.
+/// * `registres` - Le plus grand rang observé pour chaque registre.
+struct CompteurVoisinsDistincts {
+    n: usize,
+    precision: u32,
+    registres: Vec<u8>,
+}
+
+impl CompteurVoisinsDistincts {
+    /// Crée un compteur vide.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `n` - Le nombre de résultats retenus par requête (top-n).
+    /// * `precision` - Le nombre de bits désignant un registre, entre 4 et 16.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `precision` n'est pas entre 4 et 16.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut compteur = CompteurVoisinsDistincts::new(10, 12);
+    /// ```
+    fn new(n: usize, precision: u32) -> Self {
+        assert!((4..=16).contains(&precision), "la précision doit être entre 4 et 16");
+        CompteurVoisinsDistincts { n, precision, registres: vec![0; 1 << precision] }
+    }
+
+    /// Exécute une requête sur un index et ajoute au compteur les UUIDs de ses `n` premiers résultats.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for query in &requetes {
+    ///     compteur.observer(&db, query);
+    /// }
+    /// println!("{} vecteurs distincts servis", compteur.estimation_voisins_distincts());
+    /// ```
+    fn observer<I: RechercheApprochee>(&mut self, index: &I, query: &[f32]) {
+        for (uuid, _) in index.rechercher(query, self.n) {
+            self.ajouter(&uuid);
+        }
+    }
+
+    /// Ajoute un UUID au compteur ; ajouter plusieurs fois le même UUID ne change pas l'estimation.
+    fn ajouter(&mut self, uuid: &Uuid) {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let mut hacheur = DefaultHasher::new();
+        uuid.hash(&mut hacheur);
+        let hachage = hacheur.finish();
+
+        let registre = (hachage >> (64 - self.precision)) as usize;
+        // Rang du premier bit à 1 après les bits du registre, plafonné s'ils sont tous nuls
+        let reste = hachage << self.precision;
+        let rang = (reste.leading_zeros() + 1).min(64 - self.precision + 1) as u8;
+        self.registres[registre] = self.registres[registre].max(rang);
+    }
+
+    /// Estime le nombre d'UUIDs distincts ajoutés depuis la création du compteur.
+    /// 
+    /// L'estimateur harmonique de HyperLogLog est utilisé, avec la correction par comptage des
+    /// registres vides pour les petites cardinalités.
+    fn estimation_voisins_distincts(&self) -> u64 {
+        let m = self.registres.len() as f64;
+        let alpha = match self.registres.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let somme: f64 = self.registres.iter().map(|&rang| 2f64.powi(-i32::from(rang))).sum();
+        let estimation = alpha * m * m / somme;
+
+        let vides = self.registres.iter().filter(|&&rang| rang == 0).count();
+        if estimation <= 2.5 * m && vides > 0 {
+            (m * (m / vides as f64).ln()).round() as u64
+        } else {
+            estimation.round() as u64
+        }
+    }
+}
+
+/// Somme courante de vecteurs, qui permet de tenir un centroïde à jour sans tout recalculer.
+/// 
+/// Les sommes sont accumulées en `f64` pour limiter les erreurs d'arrondi au fil des mises à jour.
+/// 
+/// # Champs
+/// 
+/// * `somme` - La somme composante par composante des vecteurs ajoutés.
+/// * `nombre` - Le nombre de vecteurs ajoutés.
+#[derive(Debug, Clone, Default)]
+struct SommeCourante {
+    somme: Vec<f64>,
+    nombre: usize,
+}
+
+impl SommeCourante {
+    /// Ajoute un vecteur à la somme.
+    fn ajouter(&mut self, v: &[f32]) {
+        if self.nombre == 0 {
+            self.somme = vec![0.0; v.len()];
+        }
+        for (s, x) in self.somme.iter_mut().zip(v) {
+            *s += *x as f64;
+        }
+        self.nombre += 1;
+    }
+
+    /// Retire un vecteur précédemment ajouté.
+    fn retirer(&mut self, v: &[f32]) {
+        self.nombre -= 1;
+        if self.nombre == 0 {
+            self.somme.clear();
+        } else {
+            for (s, x) in self.somme.iter_mut().zip(v) {
+                *s -= *x as f64;
+            }
+        }
+    }
+
+    /// Retourne le vecteur moyen, ou `None` si aucun vecteur n'a été ajouté.
+    fn moyenne(&self) -> Option<Vec<f32>> {
+        (self.nombre > 0).then(|| self.somme.iter().map(|s| (s / self.nombre as f64) as f32).collect())
+    }
+}
+
+/// Moyenne et variance courantes de chaque dimension, tenues à jour par l'algorithme de Welford.
+/// 
+/// Chaque ajout met à jour la moyenne et la somme des carrés des écarts à la moyenne en une passe,
+/// sans accumuler de grandes sommes de carrés sujettes aux annulations numériques.
+/// 
+/// # Champs
+/// 
+/// * `nombre` - Le nombre de vecteurs ajoutés.
+/// * `moyenne` - La moyenne de chaque dimension.
+/// * `m2` - La somme des carrés des écarts à la moyenne de chaque dimension.
+#[derive(Debug, Clone, Default)]
+struct StatistiquesCourantes {
+    nombre: usize,
+    moyenne: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl StatistiquesCourantes {
+    /// Ajoute un vecteur aux statistiques.
+    fn ajouter(&mut self, v: &[f32]) {
+        if self.nombre == 0 {
+            self.moyenne = vec![0.0; v.len()];
+            self.m2 = vec![0.0; v.len()];
+        }
+        self.nombre += 1;
+        let nombre = self.nombre as f64;
+        for ((moyenne, m2), &x) in self.moyenne.iter_mut().zip(&mut self.m2).zip(v) {
+            let x = f64::from(x);
+            let ecart = x - *moyenne;
+            *moyenne += ecart / nombre;
+            *m2 += ecart * (x - *moyenne);
+        }
+    }
+
+    /// Retire des statistiques un vecteur précédemment ajouté, en inversant la mise à jour de Welford.
+    /// 
+    /// Retirer le dernier vecteur remet les statistiques à zéro ; les sommes de carrés que les erreurs
+    /// d'arrondi rendraient légèrement négatives sont ramenées à zéro.
+    fn retirer(&mut self, v: &[f32]) {
+        if self.nombre <= 1 {
+            *self = StatistiquesCourantes::default();
+            return;
+        }
+        self.nombre -= 1;
+        let nombre = self.nombre as f64;
+        for ((moyenne, m2), &x) in self.moyenne.iter_mut().zip(&mut self.m2).zip(v) {
+            let x = f64::from(x);
+            let ecart = x - *moyenne;
+            *moyenne -= ecart / nombre;
+            *m2 = (*m2 - ecart * (x - *moyenne)).max(0.0);
+        }
+    }
+
+    /// Retourne la moyenne et l'écart type (de population) de chaque dimension, ou `None` si aucun
+    /// vecteur n'a été ajouté.
+    fn moyenne_et_ecart_type(&self) -> Option<(Vec<f32>, Vec<f32>)> {
+        if self.nombre == 0 {
+            return None;
+        }
+        let moyenne = self.moyenne.iter().map(|&m| m as f32).collect();
+        let ecart_type = self
+            .m2
+            .iter()
+            .map(|&m2| (m2 / self.nombre as f64).max(0.0).sqrt() as f32)
+            .collect();
+        Some((moyenne, ecart_type))
+    }
+}
+
+/// Esquisse de Greenwald-Khanna, qui estime les quantiles d'un flux de valeurs en mémoire bornée.
+/// 
+/// L'esquisse conserve un sous-ensemble trié des valeurs vues, chacune accompagnée d'un encadrement
+/// de son rang. Des tuples voisins sont fusionnés dès que l'encadrement le permet, si bien que la
+/// mémoire utilisée croît en `O(log(ε·n) / ε)` et non avec le nombre de valeurs. Tout quantile
+/// retourné a un rang qui s'écarte d'au plus `ε·n` du rang exact.
+/// 
+/// # Champs
+/// 
+/// * `epsilon` - L'erreur de rang tolérée, en proportion du nombre de valeurs.
+/// * `nombre` - Le nombre de valeurs ajoutées.
+/// * `tuples` - Les triplets `(valeur, g, delta)` triés par valeur : `g` est l'écart entre le rang
+///   minimal de la valeur et celui de la précédente, `delta` l'incertitude sur son rang.
+#[derive(Debug, Clone)]
+struct EsquisseGK {
+    epsilon: f64,
+    nombre: usize,
+    tuples: Vec<(f32, usize, usize)>,
+}
+
+impl EsquisseGK {
+    /// Crée une esquisse vide garantissant une erreur de rang d'au plus `epsilon · n`.
+    fn new(epsilon: f32) -> Self {
+        assert!(epsilon > 0.0 && epsilon < 1.0, "epsilon doit être dans ]0, 1[");
+        EsquisseGK { epsilon: f64::from(epsilon), nombre: 0, tuples: Vec::new() }
+    }
+
+    /// Ajoute une valeur à l'esquisse.
+    fn ajouter(&mut self, valeur: f32) {
+        let position = self.tuples.partition_point(|&(v, _, _)| v < valeur);
+        // Une nouvelle valeur extrême a un rang exact
+        let delta = if position == 0 || position == self.tuples.len() {
+            0
+        } else {
+            self.seuil()
+        };
+        self.tuples.insert(position, (valeur, 1, delta));
+        self.nombre += 1;
+
+        let periode = (1.0 / (2.0 * self.epsilon)).floor().max(1.0) as usize;
+        if self.nombre.is_multiple_of(periode) {
+            self.compresser();
+        }
+    }
+
+    /// Capacité d'un tuple, `⌊2ε·n⌋`, qui borne `g + delta` après fusion.
+    fn seuil(&self) -> usize {
+        (2.0 * self.epsilon * self.nombre as f64).floor() as usize
+    }
+
+    /// Fusionne les tuples voisins dont l'union respecte la capacité, en gardant les extrêmes.
+    fn compresser(&mut self) {
+        let seuil = self.seuil();
+        for i in (1..self.tuples.len().saturating_sub(1)).rev() {
+            let (_, g, _) = self.tuples[i];
+            let (_, g_suivant, delta_suivant) = self.tuples[i + 1];
+            if g + g_suivant + delta_suivant <= seuil {
+                self.tuples[i + 1].1 += g;
+                self.tuples.remove(i);
+            }
+        }
+    }
+
+    /// Retourne une valeur dont le rang est à au plus `ε·n` du rang `⌈phi·n⌉`, ou `None` si aucune
+    /// valeur n'a été ajoutée.
+    fn quantile(&self, phi: f32) -> Option<f32> {
+        let rang = (f64::from(phi) * self.nombre as f64).ceil().max(1.0);
+        let marge = self.epsilon * self.nombre as f64;
+
+        let mut rang_min = 0usize;
+        for &(valeur, g, delta) in &self.tuples {
+            rang_min += g;
+            let rang_max = (rang_min + delta) as f64;
+            if rang - rang_min as f64 <= marge && rang_max - rang <= marge {
+                return Some(valeur);
+            }
+        }
+        self.tuples.last().map(|&(valeur, _, _)| valeur)
+    }
+}
+
+/// Moyenne et matrice des co-moments courantes des vecteurs, tenues à jour par l'algorithme de
+/// Welford généralisé aux paires de dimensions.
+/// 
+/// La matrice de covariance (de population) s'en déduit en divisant les co-moments par le nombre
+/// de vecteurs. Chaque ajout ou retrait coûte `O(D²)` et la mémoire occupée est de `D²` flottants.
+/// 
+/// # Champs
+/// 
+/// * `nombre` - Le nombre de vecteurs ajoutés.
+/// * `moyenne` - La moyenne de chaque dimension.
+/// * `comoments` - La somme, sur les vecteurs, des produits des écarts à la moyenne de chaque paire
+///   de dimensions, ligne par ligne.
+#[derive(Debug, Clone, Default)]
+struct CovarianceCourante {
+    nombre: usize,
+    moyenne: Vec<f64>,
+    comoments: Vec<Vec<f64>>,
+}
+
+impl CovarianceCourante {
+    /// Ajoute un vecteur à la covariance.
+    fn ajouter(&mut self, v: &[f32]) {
+        if self.nombre == 0 {
+            self.moyenne = vec![0.0; v.len()];
+            self.comoments = vec![vec![0.0; v.len()]; v.len()];
+        }
+        self.nombre += 1;
+        let nombre = self.nombre as f64;
+        let ecarts: Vec<f64> = v.iter().zip(&self.moyenne).map(|(&x, m)| f64::from(x) - m).collect();
+        for (moyenne, ecart) in self.moyenne.iter_mut().zip(&ecarts) {
+            *moyenne += ecart / nombre;
+        }
+        // Écarts à la nouvelle moyenne, colinéaires aux précédents : la mise à jour reste symétrique
+        let ecarts_nouveaux: Vec<f64> = v.iter().zip(&self.moyenne).map(|(&x, m)| f64::from(x) - m).collect();
+        for (ligne, ei) in self.comoments.iter_mut().zip(&ecarts) {
+            for (case, ej) in ligne.iter_mut().zip(&ecarts_nouveaux) {
+                *case += ei * ej;
+            }
+        }
+    }
+
+    /// Retire de la covariance un vecteur précédemment ajouté, en inversant la mise à jour de
+    /// Welford.
+    /// 
+    /// Retirer le dernier vecteur remet la covariance à zéro ; les variances que les erreurs
+    /// d'arrondi rendraient légèrement négatives sont ramenées à zéro.
+    fn retirer(&mut self, v: &[f32]) {
+        if self.nombre <= 1 {
+            *self = CovarianceCourante::default();
+            return;
+        }
+        let ecarts_actuels: Vec<f64> = v.iter().zip(&self.moyenne).map(|(&x, m)| f64::from(x) - m).collect();
+        self.nombre -= 1;
+        let nombre = self.nombre as f64;
+        for (moyenne, ecart) in self.moyenne.iter_mut().zip(&ecarts_actuels) {
+            *moyenne -= ecart / nombre;
+        }
+        let ecarts_anciens: Vec<f64> = v.iter().zip(&self.moyenne).map(|(&x, m)| f64::from(x) - m).collect();
+        for (i, (ligne, ei)) in self.comoments.iter_mut().zip(&ecarts_anciens).enumerate() {
+            for (case, ej) in ligne.iter_mut().zip(&ecarts_actuels) {
+                *case -= ei * ej;
+            }
+            ligne[i] = ligne[i].max(0.0);
+        }
+    }
+
+    /// Retourne la moyenne et la matrice de covariance (de population), ou `None` si aucun vecteur
+    /// n'a été ajouté.
+    fn moyenne_et_covariance(&self) -> Option<(Vec<f64>, Vec<Vec<f64>>)> {
+        if self.nombre == 0 {
+            return None;
+        }
+        let nombre = self.nombre as f64;
+        let covariance = self
+            .comoments
+            .iter()
+            .map(|ligne| ligne.iter().map(|c| c / nombre).collect())
+            .collect();
+        Some((self.moyenne.clone(), covariance))
+    }
+}
+
+/// Backend de stockage des vecteurs d'une `Db`.
+/// 
+/// La logique de recherche de `Db` ne passe que par ces méthodes, ce qui permet de remplacer le
+/// `HashMap` en mémoire utilisé par défaut par un autre stockage (base clé/valeur sur disque, fichier
+/// projeté en mémoire…) sans la modifier.
+trait VectorStore {
+    /// Retourne le vecteur associé à un UUID, s'il existe.
+    fn get(&self, uuid: &Uuid) -> Option<&[f32]>;
+
+    /// Associe un vecteur à un UUID et retourne l'ancien vecteur, s'il y en avait un.
+    fn insert(&mut self, uuid: Uuid, embedding: Vec<f32>) -> Option<Vec<f32>>;
+
+    /// Supprime le vecteur associé à un UUID et le retourne, s'il existait.
+    fn remove(&mut self, uuid: &Uuid) -> Option<Vec<f32>>;
+
+    /// Parcourt toutes les entrées, dans un ordre quelconque.
+    fn iter(&self) -> Box<dyn Iterator<Item = (&Uuid, &[f32])> + '_>;
+
+    /// Retourne le nombre de vecteurs stockés.
+    fn len(&self) -> usize;
+
+    /// Indique si le stockage est vide.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Indique si un vecteur est associé à l'UUID.
+    fn contains(&self, uuid: &Uuid) -> bool {
+        self.get(uuid).is_some()
+    }
+}
+
+impl VectorStore for HashMap<Uuid, Vec<f32>> {
+    fn get(&self, uuid: &Uuid) -> Option<&[f32]> {
+        HashMap::get(self, uuid).map(Vec::as_slice)
+    }
+
+    fn insert(&mut self, uuid: Uuid, embedding: Vec<f32>) -> Option<Vec<f32>> {
+        HashMap::insert(self, uuid, embedding)
+    }
+
+    fn remove(&mut self, uuid: &Uuid) -> Option<Vec<f32>> {
+        HashMap::remove(self, uuid)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&Uuid, &[f32])> + '_> {
+        Box::new(HashMap::iter(self).map(|(uuid, embedding)| (uuid, embedding.as_slice())))
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+/// Modèle capable de transformer un texte en vecteur d'embarquement, pour `Db::insert_text`.
+/// 
+/// La base ne dépend d'aucun modèle en particulier : il suffit d'implémenter ce trait pour brancher
+/// un modèle réel, ou un substitut déterministe comme `EmbedderHachage`.
+trait Embedder {
+    /// Calcule le vecteur d'embarquement d'un texte, de longueur `dimension()`.
+    fn embed(&self, text: &str) -> Vec<f32>;
+
+    /// Retourne la dimension des vecteurs produits.
+    fn dimension(&self) -> usize;
+}
+
+/// `Embedder` trivial fondé sur le hachage des mots, utile pour des tests sans modèle.
+/// 
+/// Chaque mot (séparé par des blancs, mis en minuscules) est haché vers une dimension et un signe,
+/// et y ajoute `±1` ; le vecteur est ensuite normalisé. Deux textes partageant des mots ont donc des
+/// vecteurs proches, sans aucune notion de sens. Le résultat est déterministe pour une version de
+/// Rust donnée.
+/// 
+/// # Champs
+/// 
+/// * `dimension` - La dimension des vecteurs produits.
+#[derive(Debug, Clone, Copy)]
+struct EmbedderHachage {
+    dimension: usize,
+}
+
+impl Embedder for EmbedderHachage {
+    fn embed(&self, text: &str) -> Vec<f32> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let mut vecteur = vec![0.0f32; self.dimension];
+        if self.dimension == 0 {
+            return vecteur;
+        }
+        for mot in text.split_whitespace() {
+            let mut hacheur = DefaultHasher::new();
+            mot.to_lowercase().hash(&mut hacheur);
+            let hachage = hacheur.finish();
+            let signe = if hachage >> 63 == 0 { 1.0 } else { -1.0 };
+            vecteur[(hachage % self.dimension as u64) as usize] += signe;
+        }
+
+        let longueur = norme(&vecteur);
+        if longueur > 0.0 {
+            for x in &mut vecteur {
+                *x /= longueur;
+            }
+        }
+        vecteur
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// Structure représentant une base de données simple avec des UUIDs associés à des vecteurs d'embarquement.
+/// 
+/// La structure permet d'insérer des vecteurs et de rechercher les vecteurs les plus similaires à une requête donnée.
+/// 
+/// # Champs
+/// 
+/// * `inner` - Le stockage qui associe un identifiant unique (UUID) à un vecteur d'embarquement. Par
+///   défaut, un `HashMap<Uuid, Vec<f32>>` en mémoire ; tout type implémentant `VectorStore` convient.
+/// * `sequences` - Le numéro d'insertion de chaque UUID, utilisé pour départager les scores égaux.
+/// * `prochaine_sequence` - Le numéro qui sera attribué à la prochaine insertion.
+/// * `horodatages` - La date d'insertion de chaque UUID, utilisée par la décroissance temporelle.
+/// * `reservoir` - L'échantillon aléatoire des insertions, s'il a été activé par `avec_reservoir`.
+/// * `metrique` - La mesure utilisée par défaut pour comparer des vecteurs (cosinus par défaut).
+/// * `metadonnees` - Les métadonnées associées aux UUIDs insérés avec `insert_avec_metadonnees`.
+/// * `centroide` - La somme courante des vecteurs, si le suivi du centroïde a été activé par
+///   `avec_centroide`.
+/// * `consultations` - Le nombre de fois où chaque UUID a été retourné par une recherche. Le compteur
+///   est protégé par un `Mutex` pour pouvoir être mis à jour par les recherches, qui ne prennent
+///   qu'une référence partagée.
+/// * `inverse_covariance` - L'inverse de la matrice de covariance des vecteurs, calculé par
+///   `distance_mahalanobis` et oublié à chaque modification de la base.
+/// * `statistiques` - La moyenne et la variance courantes de chaque dimension, si leur suivi a été
+///   activé par `avec_statistiques`.
+/// * `covariance` - La moyenne et les co-moments courants des vecteurs, si leur suivi a été activé
+///   par `avec_covariance`.
+struct Db<S = HashMap<Uuid, Vec<f32>>> {
+    inner: S,
+    sequences: HashMap<Uuid, u64>,
+    prochaine_sequence: u64,
+    horodatages: HashMap<Uuid, SystemTime>,
+    reservoir: Option<Reservoir>,
+    metrique: Metric,
+    metadonnees: HashMap<Uuid, Metadonnees>,
+    centroide: Option<SommeCourante>,
+    consultations: Mutex<HashMap<Uuid, u64>>,
+    inverse_covariance: Option<Vec<Vec<f64>>>,
+    statistiques: Option<StatistiquesCourantes>,
+    covariance: Option<CovarianceCourante>,
+}
+
+impl Db {
+    /// Crée une nouvelle base de données vide.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne une instance de `Db` initialisée avec un `HashMap` vide.
+    fn new() -> Self {
+        Db::avec_stockage(HashMap::new())
+    }
+
+    /// Lit une base écrite par `ecrire`.
+    fn lire<R: Read>(r: &mut R) -> io::Result<Db> {
+        if &lire_octets::<R, 4>(r)? != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "signature de fichier invalide"));
+        }
+        let [version] = lire_octets::<R, 1>(r)?;
+        if version != VERSION_FORMAT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("version de format non prise en charge : {}", version),
+            ));
+        }
+
+        let mut db = Db::new();
+        let nombre = u64::from_le_bytes(lire_octets(r)?);
+        for _ in 0..nombre {
+            let uuid = Uuid::from_bytes(lire_octets(r)?);
+
+            let secondes = u64::from_le_bytes(lire_octets(r)?);
+            let nanosecondes = u32::from_le_bytes(lire_octets(r)?);
+            let horodatage = UNIX_EPOCH + Duration::new(secondes, nanosecondes);
+
+            let dimension = u32::from_le_bytes(lire_octets(r)?) as usize;
+            let mut embedding = Vec::with_capacity(dimension.min(1 << 16));
+            for _ in 0..dimension {
+                embedding.push(f32::from_le_bytes(lire_octets(r)?));
+            }
+
+            db.insert_avec_horodatage(uuid, embedding, horodatage);
+
+            let [a_metadonnees] = lire_octets::<R, 1>(r)?;
+            if a_metadonnees == 1 {
+                let paires = u32::from_le_bytes(lire_octets(r)?);
+                let mut metadonnees = Metadonnees::new();
+                for _ in 0..paires {
+                    let cle = lire_chaine(r)?;
+                    metadonnees.insert(cle, lire_chaine(r)?);
+                }
+                db.metadonnees.insert(uuid, metadonnees);
+            }
+        }
+
+        Ok(db)
+    }
+
+    /// Charge une base sauvegardée avec `save`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Le chemin du fichier à lire.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la base chargée, ou une erreur `InvalidData` si le fichier n'est pas au bon format.
+    fn load<P: AsRef<Path>>(path: P) -> io::Result<Db> {
+        Db::lire(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+impl<S: VectorStore> Db<S> {
+    /// Crée une base qui stocke ses vecteurs dans le backend donné.
+    /// 
+    /// Les vecteurs déjà présents dans le backend sont repris : ils sont numérotés par UUID croissant
+    /// et horodatés à l'instant présent.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `stockage` - Le backend de stockage des vecteurs.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let db = Db::avec_stockage(HashMap::new());
+    /// ```
+    fn avec_stockage(stockage: S) -> Self {
+        let mut existants: Vec<Uuid> = stockage.iter().map(|(uuid, _)| *uuid).collect();
+        existants.sort();
+
+        let maintenant = SystemTime::now();
+        Db {
+            sequences: existants.iter().zip(0..).map(|(uuid, i)| (*uuid, i)).collect(),
+            prochaine_sequence: existants.len() as u64,
+            horodatages: existants.iter().map(|uuid| (*uuid, maintenant)).collect(),
+            inner: stockage,
+            reservoir: None,
+            metrique: Metric::Cosine,
+            metadonnees: HashMap::new(),
+            centroide: None,
+            consultations: Mutex::new(HashMap::new()),
+            inverse_covariance: None,
+            statistiques: None,
+            covariance: None,
+        }
+    }
+
+    /// Choisit la mesure utilisée par défaut par la base.
+    /// 
+    /// Elle s'applique à `similarite_entre` et sert de mesure initiale aux requêtes construites avec
+    /// `query`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let db = Db::new().avec_metrique(Metric::DotProduct);
+    /// ```
+    fn avec_metrique(mut self, metrique: Metric) -> Self {
+        self.metrique = metrique;
+        self
+    }
+
+    /// Active l'échantillonnage par réservoir des vecteurs insérés.
+    /// 
+    /// La base conserve alors, en plus de l'index complet, un échantillon uniforme d'au plus
+    /// `taille` insertions, consultable avec `reservoir`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `taille` - Le nombre maximal de vecteurs conservés dans l'échantillon.
+    /// * `seed` - La graine du tirage, pour un échantillon reproductible.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut db = Db::new().avec_reservoir(1000, 42);
+    /// ```
+    fn avec_reservoir(mut self, taille: usize, seed: u64) -> Self {
+        self.reservoir = Some(Reservoir {
+            capacite: taille,
+            vus: 0,
+            echantillon: Vec::with_capacity(taille),
+            rng: StdRng::seed_from_u64(seed),
+        });
+        self
+    }
+
+    /// Active le suivi incrémental du centroïde (vecteur moyen) de la base.
+    /// 
+    /// La somme des vecteurs est alors mise à jour à chaque insertion, ce qui rend `centroide`
+    /// immédiat et permet les recherches centrées de `trouver_centre`. Dans ce mode, tous les
+    /// vecteurs doivent avoir la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut db = Db::new().avec_centroide();
+    /// ```
+    fn avec_centroide(mut self) -> Self {
+        self.centroide = Some(SommeCourante::default());
+        self.recalculer_centroide();
+        self
+    }
+
+    /// Active le suivi incrémental de la moyenne et de la variance de chaque dimension.
+    /// 
+    /// Les statistiques sont alors mises à jour à chaque insertion et suppression, et `feature_stats`
+    /// est immédiat.
+    /// Dans ce mode, tous les vecteurs doivent avoir la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut db = Db::new().avec_statistiques();
+    /// ```
+    fn avec_statistiques(mut self) -> Self {
+        self.statistiques = Some(StatistiquesCourantes::default());
+        self.recalculer_statistiques();
+        self
+    }
+
+    /// Active le suivi incrémental de la matrice de covariance des vecteurs.
+    /// 
+    /// La covariance est alors mise à jour à chaque insertion et suppression, en `O(D²)`, au lieu
+    /// d'être recalculée en `O(N · D²)` par `matrice_covariance` ; `trouver_mahalanobis` n'a plus
+    /// qu'à l'inverser. Le suivi occupe `D²` flottants. Dans ce mode, tous les vecteurs doivent avoir
+    /// la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut db = Db::new().avec_covariance();
+    /// ```
+    fn avec_covariance(mut self) -> Self {
+        self.covariance = Some(CovarianceCourante::default());
+        self.recalculer_covariance();
+        self
+    }
+
+    /// Recalcule entièrement la covariance, si son suivi est actif.
+    fn recalculer_covariance(&mut self) {
+        if let Some(covariance) = &mut self.covariance {
+            *covariance = CovarianceCourante::default();
+            for (_, embedding) in self.inner.iter() {
+                covariance.ajouter(embedding);
+            }
+        }
+    }
+
+    /// Recalcule entièrement les statistiques par dimension, si leur suivi est actif.
+    fn recalculer_statistiques(&mut self) {
+        if let Some(statistiques) = &mut self.statistiques {
+            *statistiques = StatistiquesCourantes::default();
+            for (_, embedding) in self.inner.iter() {
+                statistiques.ajouter(embedding);
+            }
+        }
+    }
+
+    /// Recalcule entièrement la somme courante des vecteurs, si le suivi du centroïde est actif.
+    fn recalculer_centroide(&mut self) {
+        if let Some(centroide) = &mut self.centroide {
+            *centroide = SommeCourante::default();
+            for (_, embedding) in self.inner.iter() {
+                centroide.ajouter(embedding);
+            }
+        }
+    }
+
+    /// Insère un vecteur d'embarquement dans la base de données associé à un UUID unique.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID unique à associer au vecteur.
+    /// * `embedding` - Le vecteur d'embarquement à insérer.
+    /// 
+    /// Réinsérer un UUID existant remplace son vecteur et le compte comme une nouvelle insertion.
+    /// L'entrée est horodatée à l'instant présent.
+    fn insert(&mut self, uuid: Uuid, embedding: Vec<f32>) {
+        self.insert_avec_horodatage(uuid, embedding, SystemTime::now());
+    }
+
+    /// Insère un vecteur d'embarquement en lui associant une date explicite.
+    /// 
+    /// Utile pour charger des entrées dont la date de création est connue, ou pour maîtriser l'âge
+    /// des entrées dans `trouver_avec_decroissance`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID unique à associer au vecteur.
+    /// * `embedding` - Le vecteur d'embarquement à insérer.
+    /// * `horodatage` - La date associée à l'entrée.
+    fn insert_avec_horodatage(&mut self, uuid: Uuid, embedding: Vec<f32>, horodatage: SystemTime) {
+        if let Some(centroide) = &self.centroide {
+            assert!(
+                centroide.nombre == 0 || centroide.somme.len() == embedding.len(),
+                "le suivi du centroïde exige des vecteurs de même dimension"
+            );
+        }
+        if let Some(statistiques) = &self.statistiques {
+            assert!(
+                statistiques.nombre == 0 || statistiques.moyenne.len() == embedding.len(),
+                "le suivi des statistiques exige des vecteurs de même dimension"
+            );
+        }
+        if let Some(covariance) = &self.covariance {
+            assert!(
+                covariance.nombre == 0 || covariance.moyenne.len() == embedding.len(),
+                "le suivi de la covariance exige des vecteurs de même dimension"
+            );
+        }
+        if let Some(reservoir) = &mut self.reservoir {
+            reservoir.observer(uuid, &embedding);
+        }
+        if let Some(centroide) = &mut self.centroide {
+            centroide.ajouter(&embedding);
+        }
+        if let Some(statistiques) = &mut self.statistiques {
+            statistiques.ajouter(&embedding);
+        }
+        if let Some(covariance) = &mut self.covariance {
+            covariance.ajouter(&embedding);
+        }
+        self.inverse_covariance = None;
+        if let Some(ancien) = self.inner.insert(uuid, embedding) {
+            if let Some(centroide) = &mut self.centroide {
+                centroide.retirer(&ancien);
+            }
+            if let Some(statistiques) = &mut self.statistiques {
+                statistiques.retirer(&ancien);
+            }
+            if let Some(covariance) = &mut self.covariance {
+                covariance.retirer(&ancien);
+            }
+        }
+        self.sequences.insert(uuid, self.prochaine_sequence);
+        self.prochaine_sequence += 1;
+        self.horodatages.insert(uuid, horodatage);
+        self.metadonnees.remove(&uuid);
+    }
+
+    /// Insère un vecteur d'embarquement accompagné de métadonnées.
+    /// 
+    /// Les insertions sans métadonnées (`insert`, `insert_avec_horodatage`) effacent celles d'un
+    /// UUID réinséré.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID unique à associer au vecteur.
+    /// * `embedding` - Le vecteur d'embarquement à insérer.
+    /// * `metadonnees` - Les paires clé/valeur décrivant l'entrée (document source, auteur…).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut metadonnees = Metadonnees::new();
+    /// metadonnees.insert("source".to_string(), "rapport.pdf".to_string());
+    /// db.insert_avec_metadonnees(Uuid::new_v4(), embedding, metadonnees);
+    /// ```
+    fn insert_avec_metadonnees(&mut self, uuid: Uuid, embedding: Vec<f32>, metadonnees: Metadonnees) {
+        self.insert(uuid, embedding);
+        self.metadonnees.insert(uuid, metadonnees);
+    }
+
+    /// Retourne les métadonnées d'un UUID, s'il en a.
+    fn metadonnees(&self, uuid: &Uuid) -> Option<&Metadonnees> {
+        self.metadonnees.get(uuid)
+    }
+
+    /// Supprime un vecteur de la base, avec tout ce qui lui est associé.
+    /// 
+    /// Le numéro d'insertion, la date, les métadonnées et le compteur de consultations de l'UUID
+    /// sont oubliés, et le centroïde, les statistiques par dimension et la covariance suivis sont mis
+    /// à jour sans reparcourir la base. Le réservoir, qui échantillonne le flux des insertions, n'est pas modifié.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID à supprimer.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le vecteur supprimé, ou `None` si l'UUID était absent.
+    fn remove(&mut self, uuid: &Uuid) -> Option<Vec<f32>> {
+        let embedding = self.inner.remove(uuid)?;
+        self.inverse_covariance = None;
+        if let Some(centroide) = &mut self.centroide {
+            centroide.retirer(&embedding);
+        }
+        if let Some(statistiques) = &mut self.statistiques {
+            statistiques.retirer(&embedding);
+        }
+        if let Some(covariance) = &mut self.covariance {
+            covariance.retirer(&embedding);
+        }
+        self.sequences.remove(uuid);
+        self.horodatages.remove(uuid);
+        self.metadonnees.remove(uuid);
+        self.consultations
+            .get_mut()
+            .expect("les compteurs de consultation ne doivent pas être empoisonnés")
+            .remove(uuid);
+        Some(embedding)
+    }
+
+    /// Supprime plusieurs vecteurs de la base avec `remove`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuids` - Les UUIDs à supprimer ; les UUIDs absents de la base sont ignorés.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre de vecteurs effectivement présents, et donc supprimés.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let supprimes = db.remove_many(documents_invalides);
+    /// ```
+    fn remove_many<I: IntoIterator<Item = Uuid>>(&mut self, uuids: I) -> usize {
+        uuids
+            .into_iter()
+            .filter(|uuid| self.remove(uuid).is_some())
+            .count()
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à un vecteur de requête donné.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête pour lequel on recherche les vecteurs les plus similaires.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne un vecteur de tuples `(Uuid, f32)` où chaque tuple contient l'UUID d'un vecteur
+    /// et la similarité cosinus entre ce vecteur et la requête.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.trouver_les_plus_similaires(&query_embedding, 3);
+    /// ```
+    fn trouver_les_plus_similaires(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        self.trouver_les_plus_similaires_departage(query, n, TieBreak::Uuid)
+    }
+
+    /// Trouve les `n` vecteurs dont le produit scalaire avec la requête est le plus élevé.
+    /// 
+    /// Les scores ne sont pas normalisés (voir `produit_scalaire`) : à direction égale, un vecteur
+    /// stocké plus long est mieux classé, ce qui convient aux embarquements dont la norme porte une
+    /// information.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, f32)` triés par produit scalaire décroissant.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si un vecteur stocké n'a pas la dimension de la requête.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.trouver_par_produit_scalaire(&query_embedding, 3);
+    /// ```
+    fn trouver_par_produit_scalaire(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                assert_eq!(
+                    embedding.len(),
+                    query.len(),
+                    "le produit scalaire exige des vecteurs de la dimension de la requête"
+                );
+                (*uuid, produit_scalaire(query, embedding))
+            })
+            .collect();
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête en choisissant comment départager
+    /// les similarités égales.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `departage` - La règle appliquée aux résultats de même similarité (voir `TieBreak`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, f32)` triés par similarité décroissante.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.trouver_les_plus_similaires_departage(&query, 3, TieBreak::InsertionOrder);
+    /// ```
+    fn trouver_les_plus_similaires_departage(
+        &self,
+        query: &[f32],
+        n: usize,
+        departage: TieBreak,
+    ) -> Vec<(Uuid, f32)> {
+        let resultats = self.trouver_sans_compter(query, n, departage);
+        self.compter_consultations(resultats.iter().map(|(uuid, _)| uuid));
+        resultats
+    }
+
+    /// Classe les vecteurs stockés par similarité cosinus comme `trouver_les_plus_similaires_departage`,
+    /// sans incrémenter les compteurs de consultations.
+    /// 
+    /// C'est la recherche de base utilisée par les analyses internes (calibration, évaluation,
+    /// surveillance du rappel…), qui ne doivent pas influencer `conserver_top_consultes`.
+    fn trouver_sans_compter(&self, query: &[f32], n: usize, departage: TieBreak) -> Vec<(Uuid, f32)> {
+        let mut similarities: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
+            .collect();
+
+        // Trier par similarité décroissante
+        match departage {
+            TieBreak::Uuid => trier_par_score(&mut similarities),
+            TieBreak::InsertionOrder => similarities.sort_by(|a, b| {
+                b.1.partial_cmp(&a.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(self.sequences[&b.0].cmp(&self.sequences[&a.0]))
+            }),
+        }
+
+        // Retourner les n premiers
+        similarities.truncate(n);
+        similarities
+    }
+
+    /// Trouve en un seul parcours le vecteur le plus similaire et le plus dissemblable à la requête.
+    /// 
+    /// Appeler deux recherches distinctes calculerait chaque similarité deux fois ; ici chaque vecteur
+    /// n'est comparé qu'une seule fois à la requête.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `Some((plus_proche, plus_eloigne))`, chaque élément étant un tuple `(Uuid, f32)`, ou
+    /// `None` si la base est vide. Les égalités sont départagées par UUID croissant.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if let Some((proche, eloigne)) = db.extremes(&query_embedding) {
+    ///     println!("{} / {}", proche.0, eloigne.0);
+    /// }
+    /// ```
+    fn extremes(&self, query: &[f32]) -> Option<((Uuid, f32), (Uuid, f32))> {
+        let mut extremes: Option<((Uuid, f32), (Uuid, f32))> = None;
+
+        for (uuid, embedding) in self.inner.iter() {
+            let courant = (*uuid, calcul_similarite(query, embedding));
+            extremes = Some(match extremes {
+                None => (courant, courant),
+                Some((proche, eloigne)) => {
+                    let proche = if courant.1 > proche.1 || (courant.1 == proche.1 && courant.0 < proche.0) {
+                        courant
+                    } else {
+                        proche
+                    };
+                    let eloigne = if courant.1 < eloigne.1 || (courant.1 == eloigne.1 && courant.0 < eloigne.0) {
+                        courant
+                    } else {
+                        eloigne
+                    };
+                    (proche, eloigne)
+                }
+            });
+        }
+
+        extremes
+    }
+
+    /// Commence la construction d'une requête de recherche pour le vecteur donné.
+    /// 
+    /// Voir `Query` pour les options disponibles.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.query(&query_embedding).threshold(0.7).limit(10).run();
+    /// ```
+    fn query<'a>(&'a self, vecteur: &'a [f32]) -> Query<'a, S> {
+        Query {
+            db: self,
+            vecteur,
+            metrique: self.metrique,
+            seuil: None,
+            exclus: HashSet::new(),
+            limite: None,
+            ecreter_negatifs: false,
+        }
+    }
+
+    /// Estime la dimension intrinsèque des vecteurs stockés avec l'estimateur TwoNN.
+    /// 
+    /// Pour chaque point échantillonné, on calcule la distance euclidienne `r1` à son plus proche
+    /// voisin et `r2` à son deuxième plus proche voisin. L'estimateur du maximum de vraisemblance de
+    /// TwoNN (Facco et al., 2017) donne alors `d = m / Σ ln(r2 / r1)` sur les `m` points retenus.
+    /// Les points ayant un doublon exact (`r1 == 0`) sont ignorés.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `sample` - Le nombre de points utilisés pour l'estimation (plafonné au nombre de vecteurs).
+    ///   Les voisins sont toujours cherchés parmi tous les vecteurs stockés.
+    /// * `seed` - La graine du tirage, pour un échantillon reproductible.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la dimension estimée, ou `None` s'il y a moins de trois vecteurs ou qu'aucun point
+    /// échantillonné n'est exploitable.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let dimension = db.dimension_intrinseque(500, 42);
+    /// ```
+    fn dimension_intrinseque(&self, sample: usize, seed: u64) -> Option<f32> {
+        if self.inner.len() < 3 {
+            return None;
+        }
+
+        // Trier par UUID pour que le tirage ne dépende que de la graine
+        let mut points: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        points.sort_by_key(|(uuid, _)| **uuid);
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let indices = rand::seq::index::sample(&mut rng, points.len(), sample.min(points.len()));
+
+        let mut somme_log = 0.0f64;
+        let mut retenus = 0usize;
+        for i in indices.iter() {
+            let (mut r1, mut r2) = (f32::INFINITY, f32::INFINITY);
+            for (j, (_, embedding)) in points.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let distance = distance_euclidienne(points[i].1, embedding);
+                if distance < r1 {
+                    r2 = r1;
+                    r1 = distance;
+                } else if distance < r2 {
+                    r2 = distance;
+                }
+            }
+
+            if r1 > 0.0 {
+                somme_log += (r2 as f64 / r1 as f64).ln();
+                retenus += 1;
+            }
+        }
+
+        if retenus == 0 || somme_log <= 0.0 {
+            None
+        } else {
+            Some((retenus as f64 / somme_log) as f32)
+        }
+    }
+
+    /// Exporte la base dans un fichier Parquet.
+    /// 
+    /// La table contient deux colonnes, dans cet ordre : `uuid` (chaîne de caractères) et `embedding`
+    /// (liste de taille fixe de `f32`, de la dimension des vecteurs stockés). Les lignes sont triées
+    /// par UUID afin que deux exports d'une même base soient identiques.
+    /// 
+    /// Disponible uniquement avec la fonctionnalité `parquet`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Le chemin du fichier à écrire (écrasé s'il existe).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `Err(ExportError::DimensionIncoherente)` si les vecteurs n'ont pas tous la même
+    /// dimension, ou l'erreur d'écriture rencontrée.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// db.to_parquet("index.parquet")?;
+    /// ```
+    #[cfg(feature = "parquet")]
+    fn to_parquet<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), ExportError> {
+        use arrow::array::{FixedSizeListArray, Float32Array, StringArray};
+        use arrow::datatypes::{DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+        use parquet::arrow::ArrowWriter;
+        use std::sync::Arc;
+
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        let dimension = entrees.first().map_or(0, |(_, embedding)| embedding.len());
+        if let Some((_, embedding)) = entrees.iter().find(|(_, embedding)| embedding.len() != dimension) {
+            return Err(ExportError::DimensionIncoherente {
+                attendue: dimension,
+                trouvee: embedding.len(),
+            });
+        }
+
+        let uuids = StringArray::from_iter_values(entrees.iter().map(|(uuid, _)| uuid.to_string()));
+        let valeurs = Float32Array::from_iter_values(
+            entrees.iter().flat_map(|(_, embedding)| embedding.iter().copied()),
+        );
+        let champ_valeur = Arc::new(Field::new("item", DataType::Float32, false));
+        let embeddings = FixedSizeListArray::try_new(
+            champ_valeur.clone(),
+            dimension as i32,
+            Arc::new(valeurs),
+            None,
+        )?;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("uuid", DataType::Utf8, false),
+            Field::new("embedding", DataType::FixedSizeList(champ_valeur, dimension as i32), false),
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(uuids), Arc::new(embeddings)])?;
+
+        let fichier = std::fs::File::create(path)?;
+        let mut writer = ArrowWriter::try_new(fichier, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Compare cette base (l'ancien état) à une autre (le nouvel état).
+    /// 
+    /// Deux vecteurs sont considérés identiques s'ils ont la même dimension et des composantes
+    /// exactement égales.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` - Le nouvel état de la base.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne un `DbDiff` listant les UUIDs ajoutés, supprimés et modifiés.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let changements = avant.diff(&apres);
+    /// assert!(changements.supprimes.is_empty());
+    /// ```
+    fn diff<T: VectorStore>(&self, other: &Db<T>) -> DbDiff {
+        let mut diff = DbDiff::default();
+
+        for (uuid, embedding) in self.inner.iter() {
+            match other.inner.get(uuid) {
+                None => {
+                    diff.supprimes.insert(*uuid);
+                }
+                Some(nouveau) if nouveau != embedding => {
+                    diff.modifies.insert(*uuid);
+                }
+                Some(_) => {}
+            }
+        }
+
+        diff.ajoutes = other
+            .inner
+            .iter()
+            .map(|(uuid, _)| *uuid)
+            .filter(|uuid| !self.inner.contains(uuid))
+            .collect();
+
+        diff
+    }
+
+    /// Construit la table des normes des vecteurs actuellement stockés.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne un `NormCache` à reconstruire après toute modification de la base.
+    fn norm_cache(&self) -> NormCache {
+        NormCache {
+            normes: self
+                .inner
+                .iter()
+                .map(|(uuid, embedding)| (*uuid, norme(embedding)))
+                .collect(),
+        }
+    }
+
+    /// Calcule la similarité cosinus entre la requête et un vecteur stocké à partir de normes déjà
+    /// calculées.
+    /// 
+    /// Seul le produit scalaire est calculé : la norme de la requête est fournie par l'appelant (à
+    /// calculer une fois par requête) et celle du vecteur stocké est lue dans le cache.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query_norm` - La norme euclidienne de `query`.
+    /// * `query` - Le vecteur de requête.
+    /// * `uuid` - L'UUID du vecteur stocké à comparer.
+    /// * `cache` - La table des normes construite par `norm_cache`, à jour avec la base.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la similarité cosinus, ou `0.0` si l'UUID est absent de la base ou du cache, ou si
+    /// l'une des normes est nulle.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let cache = db.norm_cache();
+    /// let norme_query = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+    /// let similarity = db.cosine_with_cache(norme_query, &query, &uuid, &cache);
+    /// ```
+    fn cosine_with_cache(&self, query_norm: f32, query: &[f32], uuid: &Uuid, cache: &NormCache) -> f32 {
+        let (Some(embedding), Some(&norme_stockee)) = (self.inner.get(uuid), cache.normes.get(uuid)) else {
+            return 0.0;
+        };
+
+        if query_norm == 0.0 || norme_stockee == 0.0 {
+            0.0 // Éviter la division par zéro
+        } else {
+            produit_scalaire(query, embedding) / (query_norm * norme_stockee)
+        }
+    }
+
+    /// Trouve les `n` distributions stockées les plus proches de la requête au sens de la divergence
+    /// de Jensen-Shannon.
+    /// 
+    /// Adaptée aux vecteurs qui sont des distributions de probabilité (sorties de softmax, par
+    /// exemple), pour lesquels le cosinus est peu pertinent.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - La distribution de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` tuples `(Uuid, divergence)` triés par divergence croissante (les égalités
+    /// sont départagées par UUID croissant).
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si la requête ou un vecteur stocké n'est pas une distribution valide (voir
+    /// `jensen_shannon`).
+    fn trouver_par_jensen_shannon(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        let mut divergences: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, jensen_shannon(query, embedding)))
+            .collect();
+
+        // Trier par divergence croissante
+        divergences.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
+
+        divergences.truncate(n);
+        self.compter_consultations(divergences.iter().map(|(uuid, _)| uuid));
+        divergences
+    }
+
+    /// Sélectionne `n` résultats par pertinence marginale maximale (MMR).
+    /// 
+    /// Les résultats sont choisis un par un : à chaque étape, on retient le vecteur `x` qui maximise
+    /// `lambda · sim(x, query) - (1 - lambda) · max sim(x, s)`, le maximum portant sur les vecteurs
+    /// `s` déjà sélectionnés. Le premier résultat est toujours le plus pertinent, quel que soit
+    /// `lambda`. Avec `lambda = 1.0` on retrouve la recherche classique ; plus `lambda` est proche de
+    /// `0.0`, plus les résultats suivants sont diversifiés entre eux.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `lambda` - Le compromis entre pertinence et diversité, entre `0.0` et `1.0`.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les tuples `(Uuid, f32)` dans l'ordre de sélection, le score étant la similarité
+    /// cosinus avec la requête (et non le score MMR).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let diversifies = db.mmr(&query_embedding, 5, 0.5);
+    /// ```
+    fn mmr(&self, query: &[f32], n: usize, lambda: f32) -> Vec<(Uuid, f32)> {
+        // Trier les candidats par UUID pour un départage déterministe des égalités
+        let mut candidats: Vec<(Uuid, &[f32], f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, embedding, calcul_similarite(query, embedding)))
+            .collect();
+        candidats.sort_by_key(|(uuid, _, _)| *uuid);
+
+        // Similarité maximale de chaque candidat avec les vecteurs déjà sélectionnés
+        let mut redondance = vec![f32::NEG_INFINITY; candidats.len()];
+        let mut selectionnes = Vec::with_capacity(n.min(candidats.len()));
+
+        while selectionnes.len() < n && !candidats.is_empty() {
+            let mut meilleur = 0;
+            let mut meilleur_score = f32::NEG_INFINITY;
+            for (i, (_, _, pertinence)) in candidats.iter().enumerate() {
+                // Sans vecteur sélectionné, seule la pertinence compte
+                let score = if selectionnes.is_empty() {
+                    *pertinence
+                } else {
+                    lambda * pertinence - (1.0 - lambda) * redondance[i]
+                };
+                if score > meilleur_score {
+                    meilleur = i;
+                    meilleur_score = score;
+                }
+            }
+
+            let (uuid, choisi, pertinence) = candidats.remove(meilleur);
+            redondance.remove(meilleur);
+            selectionnes.push((uuid, pertinence));
+
+            for (i, (_, embedding, _)) in candidats.iter().enumerate() {
+                redondance[i] = redondance[i].max(calcul_similarite(choisi, embedding));
+            }
+        }
+
+        self.compter_consultations(selectionnes.iter().map(|(uuid, _)| uuid));
+        selectionnes
+    }
+
+    /// Compte les vecteurs stockés dont la similarité cosinus avec la requête atteint un seuil.
+    /// 
+    /// Aucun résultat n'est matérialisé : seul le compte est tenu, ce qui permet de mesurer la
+    /// densité du voisinage d'une requête à moindre coût.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `seuil` - La similarité minimale (incluse) pour qu'un vecteur soit compté.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre de vecteurs dont la similarité est supérieure ou égale à `seuil`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let voisins = db.compter_dans_rayon(&query_embedding, 0.8);
+    /// ```
+    fn compter_dans_rayon(&self, query: &[f32], seuil: f32) -> usize {
+        self.inner
+            .iter()
+            .filter(|(_, embedding)| calcul_similarite(query, embedding) >= seuil)
+            .count()
+    }
+
+    /// Indique, pour chaque vecteur stocké, si sa similarité cosinus avec la requête atteint un seuil.
+    /// 
+    /// Le résultat est un masque aligné sur une liste d'UUIDs triée par ordre croissant : il ne
+    /// dépend donc que du contenu de la base, et les masques de plusieurs requêtes sur une même base
+    /// (sans modification entre deux appels) se combinent position par position, par exemple avec un
+    /// ET logique, avant de matérialiser les résultats.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `seuil` - La similarité minimale (incluse) pour qu'un vecteur soit retenu.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les UUIDs triés par ordre croissant et, à la même position, `true` si la similarité du
+    /// vecteur correspondant est supérieure ou égale à `seuil`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let (uuids, proches_a) = db.masque_seuil(&query_a, 0.7);
+    /// let (_, proches_b) = db.masque_seuil(&query_b, 0.7);
+    /// let communs: Vec<Uuid> = uuids
+    ///     .iter()
+    ///     .zip(proches_a.iter().zip(&proches_b))
+    ///     .filter(|(_, (&a, &b))| a && b)
+    ///     .map(|(uuid, _)| *uuid)
+    ///     .collect();
+    /// ```
+    fn masque_seuil(&self, query: &[f32], seuil: f32) -> (Vec<Uuid>, Vec<bool>) {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        entrees
+            .into_iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding) >= seuil))
+            .unzip()
+    }
+
+    /// Trouve les `n` paires de vecteurs stockés les plus similaires entre elles.
+    /// 
+    /// Toutes les paires sont comparées (complexité en O(N²)), mais seules les meilleures sont
+    /// conservées au fil du calcul : la mémoire utilisée reste proportionnelle à `n`. Chaque paire non
+    /// ordonnée n'apparaît qu'une fois, le plus petit UUID en premier, et un vecteur n'est jamais
+    /// apparié à lui-même. Utile pour repérer les quasi-doublons d'une base de taille modérée.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `n` - Le nombre de paires à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les triplets `(uuid, uuid, similarité)` triés par similarité cosinus décroissante,
+    /// puis par UUIDs croissants en cas d'égalité.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (a, b, similarite) in db.paires_les_plus_similaires(10) {
+    ///     println!("{} ~ {} ({:.3})", a, b, similarite);
+    /// }
+    /// ```
+    fn paires_les_plus_similaires(&self, n: usize) -> Vec<(Uuid, Uuid, f32)> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        let ordre = |x: &(Uuid, Uuid, f32), y: &(Uuid, Uuid, f32)| {
+            y.2.partial_cmp(&x.2)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then((x.0, x.1).cmp(&(y.0, y.1)))
+        };
+
+        let mut paires: Vec<(Uuid, Uuid, f32)> = Vec::with_capacity(2 * n);
+        for (i, (a, premier)) in entrees.iter().enumerate() {
+            for (b, second) in &entrees[i + 1..] {
+                paires.push((**a, **b, calcul_similarite(premier, second)));
+                // Élaguer régulièrement pour garder une mémoire en O(n)
+                if paires.len() >= 2 * n {
+                    paires.sort_by(ordre);
+                    paires.truncate(n);
+                }
+            }
+        }
+
+        paires.sort_by(ordre);
+        paires.truncate(n);
+        paires
+    }
+
+    /// Calibre un seuil de similarité à partir d'un échantillon de requêtes, pour un rappel cible.
+    /// 
+    /// Pour chaque requête, les `n` plus proches voisins exacts sont calculés ; le rappel d'un seuil
+    /// `t` est la proportion de ces voisins dont la similarité atteint `t`, moyennée sur toutes les
+    /// requêtes. Le seuil retourné est le plus élevé dont le rappel moyen atteint `rappel_cible` :
+    /// un filtre `similarité >= seuil` retient alors, en moyenne, au moins cette fraction du top-n
+    /// de chaque requête. Plus le rappel visé est élevé, plus le seuil est bas.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `queries` - Les requêtes de calibration, représentatives du trafic.
+    /// * `rappel_cible` - Le rappel moyen visé, dans `[0, 1]`.
+    /// * `n` - Le nombre de voisins exacts pris en compte pour chaque requête.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le seuil de similarité cosinus, ou `-1.0` (qui retient tout) si aucune requête n'a
+    /// de voisin.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `rappel_cible` n'est pas dans `[0, 1]`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let seuil = db.seuil_pour_rappel(&requetes_de_calibration, 0.9, 10);
+    /// let voisins = db.compter_dans_rayon(&query, seuil);
+    /// ```
+    fn seuil_pour_rappel(&self, queries: &[Vec<f32>], rappel_cible: f32, n: usize) -> f32 {
+        assert!(
+            (0.0..=1.0).contains(&rappel_cible),
+            "le rappel cible doit être dans [0, 1]"
+        );
+
+        let top: Vec<Vec<(Uuid, f32)>> = queries
+            .iter()
+            .map(|query| self.trouver_sans_compter(query, n, TieBreak::Uuid))
+            .filter(|voisins| !voisins.is_empty())
+            .collect();
+        if top.is_empty() {
+            return -1.0;
+        }
+
+        // Chaque voisin pèse pour sa part du rappel moyen
+        let mut scores: Vec<(f32, f32)> = top
+            .iter()
+            .flat_map(|voisins| {
+                let poids = 1.0 / (voisins.len() * top.len()) as f32;
+                voisins.iter().map(move |&(_, score)| (score, poids))
+            })
+            .collect();
+        scores.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut rappel = 0.0f32;
+        for &(score, poids) in &scores {
+            rappel += poids;
+            // Tolérer l'erreur d'arrondi de la somme des poids
+            if rappel >= rappel_cible - 1e-6 {
+                return score;
+            }
+        }
+        scores.last().map_or(-1.0, |&(score, _)| score)
+    }
+
+    /// Construit le graphe des `k` plus proches voisins de chaque vecteur stocké.
+    /// 
+    /// Chaque vecteur est comparé à tous les autres (complexité en O(N²)) ; un vecteur n'apparaît
+    /// jamais dans sa propre liste de voisins.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de voisins à retenir pour chaque vecteur.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne une liste d'adjacence associant à chaque UUID ses `k` voisins `(Uuid, f32)`, triés
+    /// par similarité cosinus décroissante.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let graphe = db.knn_graph(10);
+    /// ```
+    fn knn_graph(&self, k: usize) -> HashMap<Uuid, Vec<(Uuid, f32)>> {
+        self.inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                let mut voisins: Vec<(Uuid, f32)> = self
+                    .inner
+                    .iter()
+                    .filter(|(autre, _)| *autre != uuid)
+                    .map(|(autre, vecteur)| (*autre, calcul_similarite(embedding, vecteur)))
+                    .collect();
+                trier_par_score(&mut voisins);
+                voisins.truncate(k);
+                (*uuid, voisins)
+            })
+            .collect()
+    }
+
+    /// Trouve les `n` vecteurs les plus pertinents en favorisant les entrées récentes.
+    /// 
+    /// Le score final est la similarité cosinus multipliée par une décroissance exponentielle de
+    /// l'âge de l'entrée : `score = cosinus · 0.5^(âge / demi_vie)`. Une entrée vieille d'une
+    /// demi-vie voit donc son score divisé par deux. Les entrées datées dans le futur ont un âge nul.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `demi_vie` - La durée au bout de laquelle le score d'une entrée est divisé par deux.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` tuples `(Uuid, score)` triés par score décroissant.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `demi_vie` est nulle.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let recents = db.trouver_avec_decroissance(&query, 10, Duration::from_secs(24 * 3600));
+    /// ```
+    fn trouver_avec_decroissance(&self, query: &[f32], n: usize, demi_vie: Duration) -> Vec<(Uuid, f32)> {
+        assert!(!demi_vie.is_zero(), "la demi-vie doit être strictement positive");
+
+        let maintenant = SystemTime::now();
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                let age = maintenant
+                    .duration_since(self.horodatages[uuid])
+                    .unwrap_or(Duration::ZERO);
+                let decroissance = 0.5f64.powf(age.as_secs_f64() / demi_vie.as_secs_f64());
+                (*uuid, calcul_similarite(query, embedding) * decroissance as f32)
+            })
+            .collect();
+
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Calcule le coefficient de silhouette moyen d'un partitionnement des vecteurs stockés.
+    /// 
+    /// C'est la moyenne des coefficients de `silhouette_par_point`. Un score proche de `1.0` indique
+    /// des groupes compacts et bien séparés, un score négatif des points mal affectés.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `assignments` - Le groupe de chaque UUID (par exemple issu d'un k-means). Les UUIDs absents
+    ///   de la base sont ignorés, de même que les vecteurs sans groupe.
+    /// * `metric` - La mesure utilisée pour calculer les distances (voir `Metric::distance`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la silhouette moyenne, ou `0.0` s'il y a moins de deux groupes.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let qualite = db.silhouette(&groupes, Metric::Euclidean);
+    /// ```
+    fn silhouette(&self, assignments: &HashMap<Uuid, usize>, metric: Metric) -> f32 {
+        let coefficients = self.silhouette_par_point(assignments, metric);
+        if coefficients.is_empty() {
+            return 0.0;
+        }
+        coefficients.values().sum::<f32>() / coefficients.len() as f32
+    }
+
+    /// Calcule le coefficient de silhouette de chaque point d'un partitionnement.
+    /// 
+    /// Pour chaque point, `a` est la distance moyenne aux autres points de son groupe et `b` la plus
+    /// petite distance moyenne aux points d'un autre groupe ; sa silhouette vaut
+    /// `(b - a) / max(a, b)`, et `0.0` s'il est seul dans son groupe. Un coefficient négatif signale
+    /// un point plus proche d'un autre groupe que du sien : trier par coefficient croissant fait
+    /// ressortir les vecteurs probablement mal affectés.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `assignments` - Le groupe de chaque UUID. Les UUIDs absents de la base sont ignorés, de même
+    ///   que les vecteurs sans groupe.
+    /// * `metric` - La mesure utilisée pour calculer les distances (voir `Metric::distance`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le coefficient de chaque UUID affecté et présent dans la base. Tous les coefficients
+    /// valent `0.0` s'il y a moins de deux groupes.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut coefficients: Vec<(Uuid, f32)> = db.silhouette_par_point(&groupes, Metric::Euclidean).into_iter().collect();
+    /// coefficients.sort_by(|a, b| a.1.total_cmp(&b.1));
+    /// let suspects: Vec<Uuid> = coefficients.iter().take_while(|(_, s)| *s < 0.0).map(|(uuid, _)| *uuid).collect();
+    /// ```
+    fn silhouette_par_point(&self, assignments: &HashMap<Uuid, usize>, metric: Metric) -> HashMap<Uuid, f32> {
+        let points: Vec<(Uuid, &[f32], usize)> = assignments
+            .iter()
+            .filter_map(|(uuid, &groupe)| self.inner.get(uuid).map(|embedding| (*uuid, embedding, groupe)))
+            .collect();
+
+        let groupes: HashSet<usize> = points.iter().map(|(_, _, groupe)| *groupe).collect();
+        if groupes.len() < 2 {
+            return points.iter().map(|(uuid, _, _)| (*uuid, 0.0)).collect();
+        }
+
+        let mut coefficients = HashMap::with_capacity(points.len());
+        for (i, (uuid, embedding, groupe)) in points.iter().enumerate() {
+            // Somme et nombre des distances vers chaque groupe
+            let mut distances: HashMap<usize, (f32, usize)> = HashMap::new();
+            for (j, (_, autre, groupe_autre)) in points.iter().enumerate() {
+                if i != j {
+                    let entree = distances.entry(*groupe_autre).or_insert((0.0, 0));
+                    entree.0 += metric.distance(embedding, autre);
+                    entree.1 += 1;
+                }
+            }
+
+            let Some(&(intra, taille)) = distances.get(groupe) else {
+                coefficients.insert(*uuid, 0.0); // Seul dans son groupe : silhouette nulle
+                continue;
+            };
+            let a = intra / taille as f32;
+            let b = distances
+                .iter()
+                .filter(|(autre_groupe, _)| *autre_groupe != groupe)
+                .map(|(_, (total, nombre))| total / *nombre as f32)
+                .fold(f32::INFINITY, f32::min);
+
+            let denominateur = a.max(b);
+            let coefficient = if denominateur > 0.0 { (b - a) / denominateur } else { 0.0 };
+            coefficients.insert(*uuid, coefficient);
+        }
+        coefficients
+    }
+
+    /// Retourne l'échantillon aléatoire des vecteurs insérés.
+    /// 
+    /// L'échantillon est uniforme sur toutes les insertions effectuées depuis l'activation (une
+    /// réinsertion compte comme un nouvel élément) et n'est pas affecté par les modifications
+    /// ultérieures de l'index.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les tuples `(Uuid, Vec<f32>)` retenus, ou une slice vide si le réservoir n'a pas été
+    /// activé avec `avec_reservoir`.
+    fn reservoir(&self) -> &[(Uuid, Vec<f32>)] {
+        self.reservoir
+            .as_ref()
+            .map_or(&[], |reservoir| reservoir.echantillon.as_slice())
+    }
+
+    /// Calcule la similarité entre deux vecteurs déjà stockés.
+    /// 
+    /// La comparaison utilise la mesure par défaut de la base (voir `avec_metrique`), sans copier les
+    /// vecteurs.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `a` - L'UUID du premier vecteur.
+    /// * `b` - L'UUID du deuxième vecteur.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le score selon `Metric::score`, ou `None` si l'un des deux UUIDs est absent.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if let Some(similarity) = db.similarite_entre(&uuid_a, &uuid_b) {
+    ///     println!("Similarité: {:.4}", similarity);
+    /// }
+    /// ```
+    fn similarite_entre(&self, a: &Uuid, b: &Uuid) -> Option<f32> {
+        let premier = self.inner.get(a)?;
+        let second = self.inner.get(b)?;
+        Some(self.metrique.score(premier, second))
+    }
+
+    /// Partitionne les vecteurs stockés en `k` groupes avec l'algorithme des k-moyennes.
+    /// 
+    /// Les centres sont initialisés par k-means++, puis affinés par l'algorithme de Lloyd jusqu'à ce
+    /// que plus aucune affectation ne change, ou au plus `iters` itérations. Un groupe qui se vide
+    /// conserve son centre précédent. Les distances sont euclidiennes.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de groupes (plafonné au nombre de vecteurs stockés).
+    /// * `iters` - Le nombre maximal d'itérations de Lloyd.
+    /// * `seed` - La graine de l'initialisation, pour un résultat reproductible.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le `Partitionnement` obtenu (vide si la base est vide ou si `k` vaut 0).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let partition = db.kmeans(8, 100, 42);
+    /// let qualite = db.silhouette(&partition.affectations, Metric::Euclidean);
+    /// ```
+    fn kmeans(&self, k: usize, iters: usize, seed: u64) -> Partitionnement {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        let points: Vec<&[f32]> = entrees.iter().map(|(_, embedding)| *embedding).collect();
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let centroides = initialiser_kmeans_pp(&points, k.min(points.len()), &mut rng, distance_carree);
+        let (groupes, centroides, inertie) = lloyd(&points, centroides, iters);
+        Partitionnement {
+            affectations: entrees.iter().map(|(uuid, _)| **uuid).zip(groupes).collect(),
+            centroides,
+            inertie,
+        }
+    }
+
+    /// Partitionne les vecteurs stockés en `k` groupes selon leur direction (k-moyennes sphériques).
+    /// 
+    /// Les vecteurs sont ramenés à une norme unitaire, puis chacun est affecté au centre de plus
+    /// grande similarité cosinus ; chaque centre est ensuite recalculé comme la moyenne de son groupe
+    /// et renormalisé sur la sphère. Contrairement à `kmeans`, la norme des vecteurs n'intervient
+    /// pas : deux vecteurs colinéaires de longueurs très différentes vont dans le même groupe, ce qui
+    /// correspond à une recherche par cosinus. Les centres sont initialisés par k-means++ avec le coût
+    /// `1 - cos`, et l'algorithme s'arrête quand plus aucune affectation ne change, ou au plus
+    /// après `iters` itérations. Un vecteur nul, sans direction, est affecté au groupe 0.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de groupes (plafonné au nombre de vecteurs stockés).
+    /// * `iters` - Le nombre maximal d'itérations.
+    /// * `seed` - La graine de l'initialisation, pour un résultat reproductible.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le groupe de chaque UUID, trié par UUID (vide si la base est vide ou si `k` vaut 0).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let groupes: HashMap<Uuid, usize> = db.spherical_kmeans(8, 100, 42).into_iter().collect();
+    /// let qualite = db.silhouette(&groupes, Metric::Cosine);
+    /// ```
+    fn spherical_kmeans(&self, k: usize, iters: usize, seed: u64) -> Vec<(Uuid, usize)> {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        if entrees.is_empty() || k == 0 {
+            return Vec::new();
+        }
+
+        let unitaire = |v: &mut Vec<f32>| {
+            let longueur = norme(v);
+            if longueur > 0.0 {
+                v.iter_mut().for_each(|x| *x /= longueur);
+            }
+        };
+        let points: Vec<Vec<f32>> = entrees
+            .iter()
+            .map(|(_, embedding)| {
+                let mut point = embedding.to_vec();
+                unitaire(&mut point);
+                point
+            })
+            .collect();
+        let vues: Vec<&[f32]> = points.iter().map(Vec::as_slice).collect();
+
+        // Sur la sphère unité, le cosinus se réduit au produit scalaire
+        let plus_proche = |centres: &[Vec<f32>], p: &[f32]| {
+            centres
+                .iter()
+                .map(|c| produit_scalaire(p, c))
+                .enumerate()
+                .fold((0, f32::NEG_INFINITY), |meilleur, courant| {
+                    if courant.1 > meilleur.1 {
+                        courant
+                    } else {
+                        meilleur
+                    }
+                })
+                .0
+        };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let cout = |a: &[f32], b: &[f32]| (1.0 - produit_scalaire(a, b)).max(0.0);
+        let mut centroides = initialiser_kmeans_pp(&vues, k.min(vues.len()), &mut rng, cout);
+        let mut groupes: Vec<usize> = vues.iter().map(|p| plus_proche(&centroides, p)).collect();
+
+        for _ in 0..iters {
+            // Recalculer chaque centre comme la direction moyenne de son groupe
+            for (indice, centre) in centroides.iter_mut().enumerate() {
+                let mut somme = vec![0.0f32; centre.len()];
+                let mut membres = 0usize;
+                for (p, _) in vues.iter().zip(&groupes).filter(|(_, &groupe)| groupe == indice) {
+                    for (s, x) in somme.iter_mut().zip(p.iter()) {
+                        *s += x;
+                    }
+                    membres += 1;
+                }
+                if membres > 0 && norme(&somme) > 0.0 {
+                    unitaire(&mut somme);
+                    *centre = somme;
+                }
+            }
+
+            let nouveaux: Vec<usize> = vues.iter().map(|p| plus_proche(&centroides, p)).collect();
+            if nouveaux == groupes {
+                break;
+            }
+            groupes = nouveaux;
+        }
+
+        entrees.iter().map(|(uuid, _)| **uuid).zip(groupes).collect()
+    }
+
+    /// Sélectionne `k` vecteurs stockés aussi éloignés que possible les uns des autres.
+    /// 
+    /// La sélection est gloutonne (échantillonnage du point le plus éloigné) : un premier vecteur
+    /// est tiré au hasard, puis on ajoute à chaque étape le vecteur dont la distance euclidienne au
+    /// plus proche des vecteurs déjà choisis est la plus grande. Le sous-ensemble obtenu couvre
+    /// l'espace occupé par la base : avec des groupes bien séparés, chacun est représenté avant qu'un
+    /// même groupe ne fournisse un deuxième vecteur. La plus petite distance entre deux vecteurs
+    /// sélectionnés est au moins la moitié de l'optimum. Complexité en O(N · k).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de vecteurs à sélectionner (plafonné au nombre de vecteurs stockés).
+    /// * `seed` - La graine du tirage du premier vecteur, pour un résultat reproductible.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les UUIDs sélectionnés, dans l'ordre de sélection.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Choisir 50 documents représentatifs à annoter
+    /// let a_annoter = db.sous_ensemble_diversifie(50, 42);
+    /// ```
+    fn sous_ensemble_diversifie(&self, k: usize, seed: u64) -> Vec<Uuid> {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        let k = k.min(entrees.len());
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let premier = rng.gen_range(0..entrees.len());
+        let mut selection = vec![*entrees[premier].0];
+        // Distance de chaque vecteur au plus proche vecteur sélectionné
+        let mut distances: Vec<f32> = entrees
+            .iter()
+            .map(|(_, embedding)| distance_euclidienne(embedding, entrees[premier].1))
+            .collect();
+        distances[premier] = f32::NEG_INFINITY;
+
+        while selection.len() < k {
+            let (choisi, _) = distances
+                .iter()
+                .enumerate()
+                .fold((0, f32::NEG_INFINITY), |meilleur, (i, &d)| {
+                    if d > meilleur.1 {
+                        (i, d)
+                    } else {
+                        meilleur
+                    }
+                });
+            selection.push(*entrees[choisi].0);
+            distances[choisi] = f32::NEG_INFINITY;
+            for (d, (_, embedding)) in distances.iter_mut().zip(&entrees) {
+                *d = d.min(distance_euclidienne(embedding, entrees[choisi].1));
+            }
+        }
+        selection
+    }
+
+    /// Calcule l'inertie d'un k-means pour chaque nombre de groupes d'une plage, afin de choisir `k`
+    /// par la méthode du coude.
+    /// 
+    /// Le premier `k` de la plage est partitionné par `kmeans` ; chaque `k` suivant repart des centres
+    /// obtenus pour `k - 1`, complétés par le point le plus éloigné de son centre, avant d'être affiné
+    /// par l'algorithme de Lloyd (au plus 100 itérations). Ce démarrage à chaud garantit une inertie
+    /// décroissante (au sens large, aux arrondis près) quand `k` augmente, ce que des k-means
+    /// indépendants, piégés chacun dans leur propre optimum local, n'assurent pas. Le « coude » de la
+    /// courbe, au-delà duquel ajouter un groupe n'apporte plus grand-chose, indique un bon nombre de
+    /// groupes.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k_range` - Les nombres de groupes à essayer (`k = 0` est ignoré).
+    /// * `seed` - La graine de l'initialisation du premier k-means.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les tuples `(k, inertie)` dans l'ordre de la plage.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (k, inertie) in db.elbow(1..11, 42) {
+    ///     println!("k = {} : {:.2}", k, inertie);
+    /// }
+    /// ```
+    fn elbow(&self, k_range: std::ops::Range<usize>, seed: u64) -> Vec<(usize, f32)> {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        let points: Vec<&[f32]> = entrees.iter().map(|(_, embedding)| *embedding).collect();
+
+        let mut precedents: Option<Vec<Vec<f32>>> = None;
+        let mut courbe = Vec::new();
+        for k in k_range.filter(|&k| k > 0) {
+            let centroides = match precedents.take() {
+                Some(mut centres) => {
+                    if centres.len() < points.len() {
+                        // Ajouter le point le plus mal représenté : l'inertie ne peut qu'y gagner
+                        let plus_eloigne = points
+                            .iter()
+                            .map(|p| centre_le_plus_proche(&centres, p).1)
+                            .enumerate()
+                            .fold((0, f32::NEG_INFINITY), |meilleur, courant| {
+                                if courant.1 > meilleur.1 {
+                                    courant
+                                } else {
+                                    meilleur
+                                }
+                            })
+                            .0;
+                        centres.push(points[plus_eloigne].to_vec());
+                    }
+                    centres
+                }
+                None => {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    initialiser_kmeans_pp(&points, k.min(points.len()), &mut rng, distance_carree)
+                }
+            };
+
+            let (_, centroides, inertie) = lloyd(&points, centroides, 100);
+            courbe.push((k, inertie));
+            precedents = Some(centroides);
+        }
+        courbe
+    }
+
+    /// Applique une transformation linéaire à tous les vecteurs stockés.
+    /// 
+    /// Chaque vecteur `x` est remplacé par `M · x`, ce qui permet par exemple d'aligner deux espaces
+    /// d'embarquement avec une rotation apprise (analyse de Procuste). Les dimensions sont toutes
+    /// vérifiées avant la première modification : en cas d'erreur, la base reste inchangée.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `matrix` - La matrice `D × D`, donnée ligne par ligne.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si la matrice n'est pas carrée ou si un vecteur stocké n'est pas de dimension `D`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Échanger les deux dimensions de chaque vecteur
+    /// db.appliquer_transformation(&[vec![0.0, 1.0], vec![1.0, 0.0]]);
+    /// ```
+    fn appliquer_transformation(&mut self, matrix: &[Vec<f32>]) {
+        let dimension = matrix.len();
+        assert!(
+            matrix.iter().all(|ligne| ligne.len() == dimension),
+            "la matrice de transformation doit être carrée"
+        );
+        assert!(
+            self.inner.iter().all(|(_, embedding)| embedding.len() == dimension),
+            "les vecteurs stockés doivent être de dimension {}",
+            dimension
+        );
+
+        let transformes: Vec<(Uuid, Vec<f32>)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                let transforme = matrix.iter().map(|ligne| produit_scalaire(ligne, embedding)).collect();
+                (*uuid, transforme)
+            })
+            .collect();
+        for (uuid, embedding) in transformes {
+            self.inner.insert(uuid, embedding);
+        }
+        self.recalculer_centroide();
+        self.recalculer_statistiques();
+        self.recalculer_covariance();
+        self.inverse_covariance = None;
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête en évitant de calculer entièrement
+    /// la similarité des candidats qui ne peuvent pas entrer dans le top-n.
+    /// 
+    /// Pour chaque candidat `x`, le produit scalaire est d'abord calculé sur le premier quart des
+    /// dimensions. L'inégalité de Cauchy-Schwarz borne la contribution des dimensions restantes par
+    /// `‖q_fin‖ · ‖x_fin‖`, où `‖x_fin‖` se déduit de la norme complète lue dans le cache. Si la
+    /// similarité maximale atteignable reste inférieure au `n`-ième meilleur score courant, le
+    /// candidat est écarté sans finir le calcul. L'élagage ne change pas le résultat : celui-ci est
+    /// identique à `trouver_les_plus_similaires`, départage des égalités compris.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `cache` - La table des normes, à jour avec la base (voir `norm_cache`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, f32)` et le nombre de candidats dont la similarité a
+    /// été calculée sur toutes les dimensions.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let cache = db.norm_cache();
+    /// let (resultats, calculs_complets) = db.trouver_avec_elagage(&query, 10, &cache);
+    /// ```
+    fn trouver_avec_elagage(&self, query: &[f32], n: usize, cache: &NormCache) -> (Vec<(Uuid, f32)>, usize) {
+        // Marge absorbant les erreurs d'arrondi de la borne
+        const MARGE: f32 = 1e-4;
+
+        if n == 0 {
+            return (Vec::new(), 0);
+        }
+
+        let prefixe = (query.len() / 4).max(1).min(query.len());
+        let norme_query = norme(query);
+        let norme_fin_query = norme(&query[prefixe..]);
+
+        let mut meilleurs: BinaryHeap<std::cmp::Reverse<Candidat>> = BinaryHeap::with_capacity(n + 1);
+        let mut calculs_complets = 0;
+
+        for (uuid, embedding) in self.inner.iter() {
+            let norme_stockee = cache.normes.get(uuid).copied().unwrap_or_else(|| norme(embedding));
+
+            let score = if norme_query == 0.0 || norme_stockee == 0.0 {
+                0.0 // Même convention que calcul_similarite
+            } else {
+                let mut produit = 0.0f32;
+                let mut norme_debut = 0.0f32;
+                for (a, b) in query[..prefixe].iter().zip(embedding) {
+                    produit += a * b;
+                    norme_debut += b * b;
+                }
+
+                let plein = meilleurs.len() == n;
+                let borne = (produit
+                    + norme_fin_query * (norme_stockee * norme_stockee - norme_debut).max(0.0).sqrt())
+                    / (norme_query * norme_stockee);
+                if plein && borne + MARGE < meilleurs.peek().expect("le tas est plein").0.score {
+                    continue;
+                }
+
+                // Terminer le produit scalaire dans le même ordre que calcul_similarite
+                for (a, b) in query[prefixe..].iter().zip(embedding.iter().skip(prefixe)) {
+                    produit += a * b;
+                }
+                calculs_complets += 1;
+                produit / (norme_query * norme_stockee)
+            };
+
+            let candidat = Candidat { score, uuid: *uuid };
+            if meilleurs.len() < n {
+                meilleurs.push(std::cmp::Reverse(candidat));
+            } else if candidat > meilleurs.peek().expect("le tas est plein").0 {
+                meilleurs.pop();
+                meilleurs.push(std::cmp::Reverse(candidat));
+            }
+        }
+
+        let mut resultats: Vec<(Uuid, f32)> = meilleurs
+            .into_iter()
+            .map(|std::cmp::Reverse(candidat)| (candidat.uuid, candidat.score))
+            .collect();
+        trier_par_score(&mut resultats);
+        self.compter_consultations(resultats.iter().map(|(uuid, _)| uuid));
+        (resultats, calculs_complets)
+    }
+
+    /// Trouve les meilleurs résultats en ne gardant qu'une entrée par groupe de métadonnées.
+    /// 
+    /// Les entrées sont classées par similarité cosinus décroissante, puis seule la meilleure de
+    /// chaque groupe est conservée, le groupe étant calculé par `key_fn` à partir des métadonnées.
+    /// On obtient ainsi des résultats variés, par exemple un seul passage par document source. Les
+    /// entrées sans métadonnées sont ignorées.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre maximal de groupes (et donc de résultats) à retourner.
+    /// * `key_fn` - La fonction qui associe un groupe aux métadonnées d'une entrée.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne au plus `n` tuples `(Uuid, f32)`, un par groupe, triés par similarité décroissante.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let par_source = db.trouver_groupe_par(&query, 5, |m| m.get("source").cloned());
+    /// ```
+    fn trouver_groupe_par<K: Eq + Hash, F: Fn(&Metadonnees) -> K>(
+        &self,
+        query: &[f32],
+        n: usize,
+        key_fn: F,
+    ) -> Vec<(Uuid, f32)> {
+        let mut scores: Vec<(Uuid, f32)> = self
+            .metadonnees
+            .keys()
+            .filter_map(|uuid| Some((*uuid, calcul_similarite(query, self.inner.get(uuid)?))))
+            .collect();
+        trier_par_score(&mut scores);
+
+        let mut groupes_vus = HashSet::new();
+        let resultats: Vec<(Uuid, f32)> = scores
+            .into_iter()
+            .filter(|(uuid, _)| groupes_vus.insert(key_fn(&self.metadonnees[uuid])))
+            .take(n)
+            .collect();
+        self.compter_consultations(resultats.iter().map(|(uuid, _)| uuid));
+        resultats
+    }
+
+    /// Estime le nombre d'entrées réellement distinctes, les quasi-doublons comptant pour une seule.
+    /// 
+    /// Les vecteurs sont parcourus par UUID croissant. Chacun rejoint le premier groupe dont le
+    /// représentant (le premier vecteur du groupe) a une similarité cosinus supérieure ou égale à
+    /// `seuil` avec lui ; sinon il fonde un nouveau groupe. Ce regroupement glouton dépend de l'ordre
+    /// de parcours, d'où le tri par UUID qui le rend déterministe.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `seuil` - La similarité à partir de laquelle deux vecteurs sont considérés comme doublons.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre de groupes formés.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let distincts = db.nb_groupes_approx(0.95);
+    /// ```
+    fn nb_groupes_approx(&self, seuil: f32) -> usize {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        let mut representants: Vec<&[f32]> = Vec::new();
+        for (_, embedding) in entrees {
+            if !representants
+                .iter()
+                .any(|representant| calcul_similarite(representant, embedding) >= seuil)
+            {
+                representants.push(embedding);
+            }
+        }
+
+        representants.len()
+    }
+
+    /// Écrit le contenu de la base dans le format binaire de `save`.
+    /// 
+    /// Les entrées sont écrites dans leur ordre d'insertion, si bien qu'un rechargement reproduit
+    /// l'ordre utilisé par `TieBreak::InsertionOrder`.
+    fn ecrire<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.ecrire_filtre(w, |_, _| true)
+    }
+
+    /// Écrit dans le format binaire de `save` les seules entrées acceptées par `pred`.
+    fn ecrire_filtre<W: Write, F: Fn(&Uuid, &[f32]) -> bool>(&self, w: &mut W, pred: F) -> io::Result<()> {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self
+            .inner
+            .iter()
+            .filter(|(uuid, embedding)| pred(uuid, embedding))
+            .collect();
+        entrees.sort_by_key(|(uuid, _)| self.sequences[uuid]);
+
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION_FORMAT])?;
+        w.write_all(&(entrees.len() as u64).to_le_bytes())?;
+
+        for (uuid, embedding) in entrees {
+            w.write_all(uuid.as_bytes())?;
+
+            let depuis_epoque = self.horodatages[uuid]
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or(Duration::ZERO);
+            w.write_all(&depuis_epoque.as_secs().to_le_bytes())?;
+            w.write_all(&depuis_epoque.subsec_nanos().to_le_bytes())?;
+
+            w.write_all(&(embedding.len() as u32).to_le_bytes())?;
+            for valeur in embedding {
+                w.write_all(&valeur.to_le_bytes())?;
+            }
+
+            match self.metadonnees.get(uuid) {
+                None => w.write_all(&[0])?,
+                Some(metadonnees) => {
+                    w.write_all(&[1])?;
+                    w.write_all(&(metadonnees.len() as u32).to_le_bytes())?;
+                    let mut paires: Vec<(&String, &String)> = metadonnees.iter().collect();
+                    paires.sort();
+                    for (cle, valeur) in paires {
+                        ecrire_chaine(w, cle)?;
+                        ecrire_chaine(w, valeur)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sauvegarde la base dans un fichier.
+    /// 
+    /// Le fichier contient, pour chaque entrée, son UUID, son vecteur, sa date d'insertion et ses
+    /// éventuelles métadonnées, dans l'ordre d'insertion. La configuration de la base (mesure par
+    /// défaut, réservoir) n'est pas sauvegardée.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Le chemin du fichier à écrire (écrasé s'il existe).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// db.save("index.tpdb")?;
+    /// let copie = Db::load("index.tpdb")?;
+    /// ```
+    fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.ecrire(&mut w)?;
+        w.flush()
+    }
+
+    /// Sauvegarde dans un fichier les seules entrées qui satisfont un prédicat.
+    /// 
+    /// Le fichier a le format de `save` et se recharge avec `load` comme une base à part entière :
+    /// on peut ainsi découper un gros index en un fichier par client sans construire de `Db`
+    /// intermédiaire en mémoire. Le prédicat reçoit le vecteur sous forme de slice, seule forme que
+    /// tous les backends `VectorStore` savent fournir.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Le chemin du fichier à écrire (écrasé s'il existe).
+    /// * `pred` - Le prédicat appliqué à chaque paire `(UUID, vecteur)`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let client: HashSet<Uuid> = documents_du_client();
+    /// db.save_subset("client.tpdb", |uuid, _| client.contains(uuid))?;
+    /// ```
+    fn save_subset<P: AsRef<Path>, F: Fn(&Uuid, &[f32]) -> bool>(&self, path: P, pred: F) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.ecrire_filtre(&mut w, pred)?;
+        w.flush()
+    }
+
+    /// Version asynchrone de `save`, pour ne pas bloquer le runtime tokio.
+    /// 
+    /// L'encodage est effectué sur le pool de threads bloquants de tokio (`spawn_blocking`), puis le
+    /// fichier est écrit avec `tokio::fs`. La base est reçue dans un `Arc` car elle doit rester
+    /// accessible depuis le thread bloquant pendant l'encodage.
+    /// 
+    /// Disponible uniquement avec la fonctionnalité `tokio`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// Arc::clone(&db).save_async("index.tpdb").await?;
+    /// ```
+    #[cfg(feature = "tokio")]
+    async fn save_async<P: AsRef<Path>>(self: std::sync::Arc<Self>, path: P) -> io::Result<()>
+    where
+        S: Send + Sync + 'static,
+    {
+        let octets = tokio::task::spawn_blocking(move || {
+            let mut octets = Vec::new();
+            self.ecrire(&mut octets).map(|_| octets)
+        })
+        .await
+        .map_err(io::Error::other)??;
+
+        tokio::fs::write(path, octets).await
+    }
+
+    /// Version asynchrone de `load`, pour ne pas bloquer le runtime tokio.
+    /// 
+    /// Le fichier est lu avec `tokio::fs`, puis décodé sur le pool de threads bloquants.
+    /// 
+    /// Disponible uniquement avec la fonctionnalité `tokio`.
+    #[cfg(feature = "tokio")]
+    async fn load_async<P: AsRef<Path>>(path: P) -> io::Result<Db> {
+        let octets = tokio::fs::read(path).await?;
+        tokio::task::spawn_blocking(move || Db::lire(&mut octets.as_slice()))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// Compte, pour une requête, combien de ses `n` meilleurs résultats tombent dans chaque groupe.
+    /// 
+    /// Une répartition concentrée sur un seul groupe signale une recherche peu diversifiée.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats considérés (ceux de `trouver_les_plus_similaires`).
+    /// * `assignments` - Le groupe de chaque UUID. Les résultats sans groupe ne sont pas comptés.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre de résultats par identifiant de groupe (les groupes absents des résultats
+    /// n'apparaissent pas).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let partition = db.kmeans(8, 100, 42);
+    /// let couverture = db.couverture_clusters(&query, 20, &partition.affectations);
+    /// ```
+    fn couverture_clusters(
+        &self,
+        query: &[f32],
+        n: usize,
+        assignments: &HashMap<Uuid, usize>,
+    ) -> HashMap<usize, usize> {
+        let mut couverture = HashMap::new();
+        for (uuid, _) in self.trouver_sans_compter(query, n, TieBreak::Uuid) {
+            if let Some(&groupe) = assignments.get(&uuid) {
+                *couverture.entry(groupe).or_insert(0) += 1;
+            }
+        }
+        couverture
+    }
+
+    /// Calcule le centroïde (vecteur moyen) des vecteurs stockés.
+    /// 
+    /// Si le suivi du centroïde est actif (voir `avec_centroide`), la valeur tenue à jour est
+    /// retournée directement ; sinon, la moyenne est calculée en parcourant toute la base.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le centroïde, ou `None` si la base est vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Sans suivi actif, panique si les vecteurs stockés n'ont pas tous la même dimension.
+    fn centroide(&self) -> Option<Vec<f32>> {
+        if let Some(centroide) = &self.centroide {
+            return centroide.moyenne();
+        }
+
+        let mut somme = SommeCourante::default();
+        for (_, embedding) in self.inner.iter() {
+            assert!(
+                somme.nombre == 0 || somme.somme.len() == embedding.len(),
+                "le centroïde exige des vecteurs de même dimension"
+            );
+            somme.ajouter(embedding);
+        }
+        somme.moyenne()
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête après soustraction du centroïde.
+    /// 
+    /// Le centroïde est retiré de la requête comme de chaque vecteur stocké avant le calcul du
+    /// cosinus. Dans un espace anisotrope, où tous les vecteurs partagent une forte composante
+    /// commune, cela supprime cette composante et fait ressortir ce qui distingue réellement les
+    /// vecteurs. Les scores obtenus diffèrent donc sensiblement de ceux de
+    /// `trouver_les_plus_similaires` et ne leur sont pas comparables.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` tuples `(Uuid, f32)` triés par similarité centrée décroissante (vide si la
+    /// base est vide).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut db = Db::new().avec_centroide();
+    /// // ... insertions ...
+    /// let resultats = db.trouver_centre(&query, 10);
+    /// ```
+    fn trouver_centre(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        let Some(centroide) = self.centroide() else {
+            return Vec::new();
+        };
+        let centrer = |v: &[f32]| -> Vec<f32> { v.iter().zip(&centroide).map(|(x, c)| x - c).collect() };
+
+        let query = centrer(query);
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(&query, &centrer(embedding))))
+            .collect();
+
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Insère un vecteur seulement si son UUID n'est pas déjà présent.
+    /// 
+    /// Contrairement à `insert`, un UUID existant n'est jamais écrasé : la collision est signalée à
+    /// l'appelant au lieu de faire disparaître silencieusement l'ancien vecteur.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID unique à associer au vecteur.
+    /// * `embedding` - Le vecteur d'embarquement à insérer.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `Err(uuid)` si l'UUID existe déjà (la base n'est alors pas modifiée).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if let Err(uuid) = db.insert_checked(uuid, embedding) {
+    ///     eprintln!("UUID en double : {}", uuid);
+    /// }
+    /// ```
+    fn insert_checked(&mut self, uuid: Uuid, embedding: Vec<f32>) -> Result<(), Uuid> {
+        if self.inner.contains(&uuid) {
+            return Err(uuid);
+        }
+        self.insert(uuid, embedding);
+        Ok(())
+    }
+
+    /// Insère un lot de vecteurs sans écraser les UUIDs existants.
+    /// 
+    /// Chaque entrée est insérée avec `insert_checked`. Les entrées en collision (avec la base ou
+    /// avec une entrée précédente du même lot) sont ignorées, les autres sont insérées.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `entrees` - Les paires `(Uuid, Vec<f32>)` à insérer.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `Err` avec la liste des UUIDs en collision, dans l'ordre du lot, s'il y en a eu.
+    fn insert_many_checked<I: IntoIterator<Item = (Uuid, Vec<f32>)>>(&mut self, entrees: I) -> Result<(), Vec<Uuid>> {
+        let collisions: Vec<Uuid> = entrees
+            .into_iter()
+            .filter_map(|(uuid, embedding)| self.insert_checked(uuid, embedding).err())
+            .collect();
+
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(collisions)
+        }
+    }
+
+    /// Empile les vecteurs stockés en un `IndexMatriciel` pour des recherches par produit
+    /// matrice-vecteur.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne l'index, à reconstruire après toute modification de la base.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs stockés n'ont pas tous la même dimension.
+    fn index_matriciel(&self) -> IndexMatriciel {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        let dimension = entrees.first().map_or(0, |(_, embedding)| embedding.len());
+        assert!(
+            entrees.iter().all(|(_, embedding)| embedding.len() == dimension),
+            "l'index matriciel exige des vecteurs de même dimension"
+        );
+
+        IndexMatriciel {
+            uuids: entrees.iter().map(|(uuid, _)| **uuid).collect(),
+            vecteurs: entrees.iter().flat_map(|(_, embedding)| embedding.iter().copied()).collect(),
+            dimension,
+            normes: entrees.iter().map(|(_, embedding)| norme(embedding)).collect(),
+        }
+    }
+
+    /// Construit l'histogramme des similarités cosinus entre la requête et les vecteurs stockés.
+    /// 
+    /// L'intervalle `[-1, 1]` est découpé en `bins` classes de même largeur ; chaque classe inclut
+    /// sa borne basse, et la dernière inclut aussi `1.0`. Les scores sortant légèrement de
+    /// l'intervalle à cause des arrondis sont rangés dans la classe extrême la plus proche.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `bins` - Le nombre de classes.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les tuples `(borne_basse, borne_haute, nombre)` par ordre croissant, ou un vecteur
+    /// vide si la base est vide ou si `bins` vaut 0.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (bas, haut, nombre) in db.histogramme_scores(&query, 20) {
+    ///     println!("[{:.2}, {:.2}) : {}", bas, haut, nombre);
+    /// }
+    /// ```
+    fn histogramme_scores(&self, query: &[f32], bins: usize) -> Vec<(f32, f32, usize)> {
+        if self.inner.is_empty() || bins == 0 {
+            return Vec::new();
+        }
+
+        let largeur = 2.0 / bins as f32;
+        let mut comptes = vec![0usize; bins];
+        for (_, embedding) in self.inner.iter() {
+            let score = calcul_similarite(query, embedding);
+            let classe = (((score + 1.0) / largeur).floor().max(0.0) as usize).min(bins - 1);
+            comptes[classe] += 1;
+        }
+
+        comptes
+            .into_iter()
+            .enumerate()
+            .map(|(i, nombre)| (-1.0 + i as f32 * largeur, -1.0 + (i + 1) as f32 * largeur, nombre))
+            .collect()
+    }
+
+    /// Trouve les `n` vecteurs les plus « centraux », c'est-à-dire les plus similaires au centroïde.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` tuples `(Uuid, f32)` triés par similarité cosinus au centroïde décroissante
+    /// (vide si la base est vide).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let representatifs = db.plus_centraux(5);
+    /// ```
+    fn plus_centraux(&self, n: usize) -> Vec<(Uuid, f32)> {
+        match self.centroide() {
+            Some(centroide) => self.trouver_sans_compter(&centroide, n, TieBreak::Uuid),
+            None => Vec::new(),
+        }
+    }
+
+    /// Trouve les `n` vecteurs les plus éloignés du centroïde, candidats naturels au statut de
+    /// valeurs aberrantes.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` tuples `(Uuid, f32)` triés par similarité cosinus au centroïde croissante :
+    /// le premier est le moins central de la base.
+    fn plus_aberrants(&self, n: usize) -> Vec<(Uuid, f32)> {
+        let Some(centroide) = self.centroide() else {
+            return Vec::new();
+        };
+
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(&centroide, embedding)))
+            .collect();
+        trier_par_score(&mut scores);
+
+        scores.into_iter().rev().take(n).collect()
+    }
+
+    /// Version faillible de `trouver_les_plus_similaires`, qui distingue une base vide d'une
+    /// recherche sans résultat.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `Err(SearchError::EmptyDatabase)` si la base ne contient aucun vecteur, sinon
+    /// `Ok` avec les mêmes résultats que `trouver_les_plus_similaires` (éventuellement vides).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// match db.try_search(&query, 10) {
+    ///     Ok(resultats) => println!("{} résultats", resultats.len()),
+    ///     Err(SearchError::EmptyDatabase) => eprintln!("index vide !"),
+    /// }
+    /// ```
+    fn try_search(&self, query: &[f32], n: usize) -> Result<Vec<(Uuid, f32)>, SearchError> {
+        if self.inner.is_empty() {
+            return Err(SearchError::EmptyDatabase);
+        }
+        Ok(self.trouver_les_plus_similaires(query, n))
+    }
+
+    /// Ramène tous les vecteurs stockés à la dimension `target_dim`, par complétion ou troncature.
+    /// 
+    /// Cela permet de mélanger des embarquements de dimensions différentes (par exemple 384 et 768),
+    /// mais change la sémantique des similarités : les zéros ajoutés ne contribuent pas au produit
+    /// scalaire, et les composantes tronquées sont perdues définitivement. Les dimensions sont
+    /// toutes vérifiées avant la première modification : en cas d'erreur, la base reste inchangée.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `target_dim` - La dimension commune à atteindre.
+    /// * `mode` - `ConformMode::Pad` pour compléter les vecteurs plus courts, ou
+    ///   `ConformMode::Truncate` pour tronquer les vecteurs plus longs.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique en mode `Pad` si un vecteur est plus long que `target_dim`, et en mode `Truncate` si un
+    /// vecteur est plus court.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Compléter les vecteurs de dimension 384 pour les aligner sur ceux de dimension 768
+    /// db.conformer_dimension(768, ConformMode::Pad);
+    /// ```
+    fn conformer_dimension(&mut self, target_dim: usize, mode: ConformMode) {
+        match mode {
+            ConformMode::Pad => assert!(
+                self.inner.iter().all(|(_, embedding)| embedding.len() <= target_dim),
+                "impossible de compléter un vecteur de dimension supérieure à {}",
+                target_dim
+            ),
+            ConformMode::Truncate => assert!(
+                self.inner.iter().all(|(_, embedding)| embedding.len() >= target_dim),
+                "impossible de tronquer un vecteur de dimension inférieure à {}",
+                target_dim
+            ),
+        }
+
+        let conformes: Vec<(Uuid, Vec<f32>)> = self
+            .inner
+            .iter()
+            .filter(|(_, embedding)| embedding.len() != target_dim)
+            .map(|(uuid, embedding)| {
+                let mut conforme = embedding.to_vec();
+                conforme.resize(target_dim, 0.0);
+                (*uuid, conforme)
+            })
+            .collect();
+        for (uuid, embedding) in conformes {
+            self.inner.insert(uuid, embedding);
+        }
+        self.recalculer_centroide();
+        self.recalculer_statistiques();
+        self.recalculer_covariance();
+        self.inverse_covariance = None;
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête en traitant la base par blocs.
+    /// 
+    /// Les similarités sont calculées `chunk` par `chunk` dans un tampon réutilisé, puis versées dans
+    /// un tas qui ne garde que les `n` meilleurs candidats : la mémoire occupée est bornée par
+    /// `chunk + n` scores au lieu d'un score par vecteur stocké. Le résultat est identique à
+    /// `trouver_les_plus_similaires`, départage des égalités compris.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `chunk` - Le nombre de vecteurs évalués par bloc.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `chunk` vaut zéro.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.trouver_les_plus_similaires_chunked(&query, 10, 4096);
+    /// ```
+    fn trouver_les_plus_similaires_chunked(&self, query: &[f32], n: usize, chunk: usize) -> Vec<(Uuid, f32)> {
+        assert!(chunk > 0, "la taille de bloc doit être strictement positive");
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let mut meilleurs: BinaryHeap<std::cmp::Reverse<Candidat>> = BinaryHeap::with_capacity(n + 1);
+        let mut bloc: Vec<Candidat> = Vec::with_capacity(chunk);
+        let mut entrees = self.inner.iter().peekable();
+
+        while entrees.peek().is_some() {
+            bloc.clear();
+            bloc.extend(entrees.by_ref().take(chunk).map(|(uuid, embedding)| Candidat {
+                score: calcul_similarite(query, embedding),
+                uuid: *uuid,
+            }));
+
+            for &candidat in &bloc {
+                if meilleurs.len() < n {
+                    meilleurs.push(std::cmp::Reverse(candidat));
+                } else if candidat > meilleurs.peek().expect("le tas est plein").0 {
+                    meilleurs.pop();
+                    meilleurs.push(std::cmp::Reverse(candidat));
+                }
+            }
+        }
+
+        let mut resultats: Vec<(Uuid, f32)> = meilleurs
+            .into_iter()
+            .map(|std::cmp::Reverse(candidat)| (candidat.uuid, candidat.score))
+            .collect();
+        trier_par_score(&mut resultats);
+        self.compter_consultations(resultats.iter().map(|(uuid, _)| uuid));
+        resultats
+    }
+
+    /// Insère un vecteur produit à la demande par une fabrique.
+    /// 
+    /// Le stockage étant en mémoire, le vecteur est matérialisé immédiatement : l'intérêt est de ne
+    /// pas avoir à le construire avant l'appel. Voir `avec_fabriques` pour construire une base entière
+    /// sans jamais détenir tous les vecteurs en dehors d'elle.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID unique à associer au vecteur.
+    /// * `f` - La fabrique appelée une seule fois pour produire le vecteur.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// db.insert_lazy(Uuid::new_v4(), || generer_embedding(768));
+    /// ```
+    fn insert_lazy<F: FnOnce() -> Vec<f32>>(&mut self, uuid: Uuid, f: F) {
+        self.insert(uuid, f());
+    }
+
+    /// Insère les vecteurs produits par une suite de fabriques, chacune n'étant appelée qu'au moment
+    /// de l'insertion de son entrée.
+    /// 
+    /// L'itérateur est consommé paresseusement : à tout instant, seul le vecteur en cours d'insertion
+    /// existe hors de la base, ce qui permet de construire de grands jeux de test sans préparer tous
+    /// les vecteurs à l'avance.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `fabriques` - Les paires `(Uuid, fabrique)` à insérer, dans l'ordre.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let fabriques = (0..100_000).map(|_| {
+    ///     let fabrique: Box<dyn FnOnce() -> Vec<f32>> = Box::new(|| generer_embedding(768));
+    ///     (Uuid::new_v4(), fabrique)
+    /// });
+    /// let db = Db::new().avec_fabriques(fabriques);
+    /// ```
+    fn avec_fabriques<I>(mut self, fabriques: I) -> Self
+    where
+        I: Iterator<Item = (Uuid, Box<dyn FnOnce() -> Vec<f32>>)>,
+    {
+        for (uuid, fabrique) in fabriques {
+            self.insert_lazy(uuid, fabrique);
+        }
+        self
+    }
+
+    /// Calcule la médiane géométrique des vecteurs stockés par l'algorithme de Weiszfeld.
+    /// 
+    /// La médiane géométrique est le point qui minimise la somme des distances euclidiennes aux
+    /// vecteurs stockés. Contrairement au centroïde, qui minimise la somme des distances au carré,
+    /// elle est peu sensible aux valeurs aberrantes. Partant du centroïde, chaque itération remplace
+    /// l'estimation `y` par la moyenne des vecteurs pondérés par `1 / ‖x - y‖`. Lorsque l'estimation
+    /// tombe exactement sur un vecteur stocké, ce poids est infini : on applique alors la correction
+    /// de Vardi et Zhang, qui exclut ce vecteur de la moyenne et ne quitte ce point que s'il n'est pas
+    /// lui-même la médiane. Les calculs sont faits en `f64`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `iters` - Le nombre maximal d'itérations ; l'algorithme s'arrête plus tôt s'il a convergé.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la médiane géométrique, ou `None` si la base est vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs stockés n'ont pas tous la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let centre_robuste = db.mediane_geometrique(100).expect("base non vide");
+    /// ```
+    fn mediane_geometrique(&self, iters: usize) -> Option<Vec<f32>> {
+        // Distance en deçà de laquelle l'estimation est confondue avec un point, ou a convergé
+        const EPSILON: f64 = 1e-10;
+
+        let mut estimation: Vec<f64> = self.centroide()?.into_iter().map(f64::from).collect();
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        for _ in 0..iters {
+            let mut somme_ponderee = vec![0.0f64; estimation.len()];
+            let mut somme_poids = 0.0f64;
+            let mut confondus = 0usize;
+
+            for (_, embedding) in &entrees {
+                let distance = embedding
+                    .iter()
+                    .zip(&estimation)
+                    .map(|(&x, y)| (f64::from(x) - y) * (f64::from(x) - y))
+                    .sum::<f64>()
+                    .sqrt();
+                if distance < EPSILON {
+                    confondus += 1;
+                    continue;
+                }
+                for (s, &x) in somme_ponderee.iter_mut().zip(embedding.iter()) {
+                    *s += f64::from(x) / distance;
+                }
+                somme_poids += 1.0 / distance;
+            }
+
+            if somme_poids == 0.0 {
+                break; // Tous les vecteurs sont confondus avec l'estimation
+            }
+            let cible: Vec<f64> = somme_ponderee.iter().map(|s| s / somme_poids).collect();
+
+            let suivante: Vec<f64> = if confondus == 0 {
+                cible
+            } else {
+                // Correction de Vardi et Zhang : ‖R‖ = somme_poids · ‖cible - y‖
+                let ecart = cible
+                    .iter()
+                    .zip(&estimation)
+                    .map(|(t, y)| (t - y) * (t - y))
+                    .sum::<f64>()
+                    .sqrt();
+                let rapport = (confondus as f64 / (somme_poids * ecart)).min(1.0);
+                cible
+                    .iter()
+                    .zip(&estimation)
+                    .map(|(t, y)| (1.0 - rapport) * t + rapport * y)
+                    .collect()
+            };
+
+            let deplacement = suivante
+                .iter()
+                .zip(&estimation)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt();
+            estimation = suivante;
+            if deplacement < EPSILON {
+                break;
+            }
+        }
+
+        Some(estimation.into_iter().map(|x| x as f32).collect())
+    }
+
+    /// Range les vecteurs stockés par colonnes en un `IndexColonnes`, pour des recherches sur de
+    /// grands lots.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne l'index, à reconstruire après toute modification de la base.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs stockés n'ont pas tous la même dimension.
+    fn index_colonnes(&self) -> IndexColonnes {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+
+        let dimension = entrees.first().map_or(0, |(_, embedding)| embedding.len());
+        assert!(
+            entrees.iter().all(|(_, embedding)| embedding.len() == dimension),
+            "l'index par colonnes exige des vecteurs de même dimension"
+        );
+
+        IndexColonnes {
+            uuids: entrees.iter().map(|(uuid, _)| **uuid).collect(),
+            colonnes: (0..dimension)
+                .flat_map(|d| entrees.iter().map(move |(_, embedding)| embedding[d]))
+                .collect(),
+            dimension,
+            normes: entrees.iter().map(|(_, embedding)| norme(embedding)).collect(),
+        }
+    }
+
+    /// Incrémente le compteur de consultations des UUIDs retournés par une recherche.
+    fn compter_consultations<'u>(&self, uuids: impl Iterator<Item = &'u Uuid>) {
+        let mut consultations = self
+            .consultations
+            .lock()
+            .expect("les compteurs de consultation ne doivent pas être empoisonnés");
+        for uuid in uuids {
+            *consultations.entry(*uuid).or_insert(0) += 1;
+        }
+    }
+
+    /// Retourne le nombre de fois où un UUID a été retourné par une recherche.
+    /// 
+    /// Seules les recherches destinées à servir des résultats sont comptées :
+    /// `trouver_les_plus_similaires`, `trouver_les_plus_similaires_departage`, `try_search`,
+    /// `trouver_les_plus_similaires_chunked`, `trouver_avec_elagage`,
+    /// `trouver_les_plus_similaires_parallele`, `Query::run`, `trouver_par_produit_scalaire`,
+    /// `trouver_par_jensen_shannon`, `mmr`, `trouver_avec_decroissance`, `trouver_groupe_par`,
+    /// `trouver_centre`, `trouver_adaptatif`, `trouver_avec_poids`, `trouver_dans_bande`,
+    /// `trouver_avec_percentile`, `trouver_positif_negatif`, `trouver_mahalanobis` et
+    /// `trouver_par_paliers` (chaque UUID de chaque palier). Les analyses qui reposent sur une
+    /// recherche (`seuil_pour_rappel`, `evaluer`, `plus_centraux`, `couverture_clusters`,
+    /// `trouver_classe`, `trouver_les_plus_similaires_quantise`, ou la `Db` utilisée comme
+    /// `RechercheApprochee` par `MoniteurRappel` et `CompteurVoisinsDistincts`) passent par
+    /// `trouver_sans_compter` et ne modifient pas les compteurs.
+    /// Les compteurs vivent en mémoire : ils ne sont pas sauvegardés par `save`.
+    fn consultations(&self, uuid: &Uuid) -> u64 {
+        self.consultations
+            .lock()
+            .expect("les compteurs de consultation ne doivent pas être empoisonnés")
+            .get(uuid)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Réduit la base aux `k` vecteurs les plus souvent retournés par les recherches.
+    /// 
+    /// Les UUIDs sont classés par nombre de consultations décroissant (voir `consultations`), les
+    /// égalités étant départagées par UUID croissant ; un vecteur jamais retourné compte zéro
+    /// consultation. Les autres vecteurs sont supprimés avec `remove`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de vecteurs à conserver.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le nombre de vecteurs supprimés.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Ne garder que les 10 000 vecteurs les plus demandés
+    /// let supprimes = db.conserver_top_consultes(10_000);
+    /// ```
+    fn conserver_top_consultes(&mut self, k: usize) -> usize {
+        let mut classement: Vec<(Uuid, u64)> = {
+            let consultations = self
+                .consultations
+                .get_mut()
+                .expect("les compteurs de consultation ne doivent pas être empoisonnés");
+            self.inner
+                .iter()
+                .map(|(uuid, _)| (*uuid, consultations.get(uuid).copied().unwrap_or(0)))
+                .collect()
+        };
+        classement.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let a_supprimer: Vec<Uuid> = classement.into_iter().skip(k).map(|(uuid, _)| uuid).collect();
+        for uuid in &a_supprimer {
+            self.remove(uuid);
+        }
+        a_supprimer.len()
+    }
+
+    /// Trouve les meilleurs résultats avec un seuil qui s'adapte à la distribution des scores.
+    /// 
+    /// Les similarités cosinus de tous les vecteurs stockés sont calculées, ainsi que leur moyenne et
+    /// leur écart type `σ` (de population). Seuls les résultats dont le score est au moins
+    /// `meilleur_score - z · σ` sont retournés : quand quelques résultats se détachent nettement, ils
+    /// sont les seuls retenus, sans seuil fixe à régler requête par requête.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `max_n` - Le nombre maximal de résultats à retourner.
+    /// * `z` - L'écart au meilleur score toléré, en nombre d'écarts types.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne au plus `max_n` tuples `(Uuid, f32)` triés comme ceux de
+    /// `trouver_les_plus_similaires`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Les résultats à moins d'un demi écart type du meilleur
+    /// let resultats = db.trouver_adaptatif(&query, 20, 0.5);
+    /// ```
+    fn trouver_adaptatif(&self, query: &[f32], max_n: usize, z: f32) -> Vec<(Uuid, f32)> {
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
+            .collect();
+        if scores.is_empty() {
+            return scores;
+        }
+        trier_par_score(&mut scores);
+
+        let nombre = scores.len() as f32;
+        let moyenne = scores.iter().map(|(_, score)| score).sum::<f32>() / nombre;
+        let variance = scores.iter().map(|(_, score)| (score - moyenne) * (score - moyenne)).sum::<f32>() / nombre;
+        let seuil = scores[0].1 - z * variance.sqrt();
+
+        let resultats: Vec<(Uuid, f32)> = scores
+            .into_iter()
+            .take_while(|(_, score)| *score >= seuil)
+            .take(max_n)
+            .collect();
+        self.compter_consultations(resultats.iter().map(|(uuid, _)| uuid));
+        resultats
+    }
+
+    /// Calcule un poids par dimension, à la manière de l'IDF en recherche documentaire.
+    /// 
+    /// Une dimension est dite active dans un vecteur si sa composante vaut plus de `1e-6` en valeur
+    /// absolue. Si `N` est le nombre de vecteurs stockés et `df` le nombre de vecteurs où la dimension
+    /// est active, son poids vaut `ln((1 + N) / (1 + df))`. Une dimension active partout (commune, donc
+    /// peu informative) reçoit un poids nul ; une dimension rarement active reçoit un poids élevé, au
+    /// plus `ln(1 + N)`.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne un poids positif ou nul par dimension, ou un vecteur vide si la base est vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs stockés n'ont pas tous la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let poids = db.calcul_poids_idf();
+    /// let resultats = db.trouver_avec_poids(&query, 10, &poids);
+    /// ```
+    fn calcul_poids_idf(&self) -> Vec<f32> {
+        // Valeur absolue au-delà de laquelle une composante est considérée comme active
+        const EPSILON: f32 = 1e-6;
+
+        let mut actifs: Option<Vec<usize>> = None;
+        let mut nombre = 0usize;
+        for (_, embedding) in self.inner.iter() {
+            let actifs = actifs.get_or_insert_with(|| vec![0; embedding.len()]);
+            assert_eq!(
+                actifs.len(),
+                embedding.len(),
+                "les poids IDF exigent des vecteurs de même dimension"
+            );
+            for (compte, x) in actifs.iter_mut().zip(embedding) {
+                if x.abs() > EPSILON {
+                    *compte += 1;
+                }
+            }
+            nombre += 1;
+        }
+
+        actifs
+            .unwrap_or_default()
+            .into_iter()
+            .map(|df| ((1.0 + nombre as f32) / (1.0 + df as f32)).ln())
+            .collect()
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête selon un cosinus pondéré par dimension.
+    /// 
+    /// Chaque vecteur stocké est comparé à la requête par `cosine_pondere`, qui multiplie chaque
+    /// terme des sommes du cosinus par le poids de sa dimension. Les poids peuvent venir de
+    /// `calcul_poids_idf`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `poids` - Le poids, positif ou nul, de chaque dimension.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, f32)` triés par similarité pondérée décroissante.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `poids` n'a pas la longueur de la requête ou contient un poids négatif.
+    fn trouver_avec_poids(&self, query: &[f32], n: usize, poids: &[f32]) -> Vec<(Uuid, f32)> {
+        assert_eq!(poids.len(), query.len(), "il faut un poids par dimension de la requête");
+        assert!(poids.iter().all(|&w| w >= 0.0), "les poids doivent être positifs ou nuls");
+
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, cosine_pondere(query, embedding, poids)))
+            .collect();
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Calcule une empreinte du contenu de la base, indépendante de l'ordre d'insertion.
+    /// 
+    /// Chaque paire `(UUID, vecteur)` est hachée séparément (les composantes par leur représentation
+    /// binaire exacte), puis les hachages sont combinés par OU exclusif : le résultat ne dépend donc
+    /// pas de l'ordre de parcours du stockage. Deux bases de même contenu ont la même empreinte ; les
+    /// métadonnées, dates et compteurs ne sont pas pris en compte. L'empreinte est stable d'une
+    /// exécution à l'autre, mais pas garantie entre deux versions de Rust (elle repose sur
+    /// `DefaultHasher`) : elle convient à l'invalidation de caches, pas à un stockage durable.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne l'empreinte sur 64 bits (`0` pour une base vide).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let avant = db.empreinte();
+    /// db.insert(Uuid::new_v4(), embedding);
+    /// assert_ne!(avant, db.empreinte());
+    /// ```
+    fn empreinte(&self) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        self.inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                let mut hacheur = DefaultHasher::new();
+                uuid.hash(&mut hacheur);
+                embedding.len().hash(&mut hacheur);
+                for x in embedding {
+                    x.to_bits().hash(&mut hacheur);
+                }
+                hacheur.finish()
+            })
+            .fold(0, |empreinte, hachage| empreinte ^ hachage)
+    }
+
+    /// Trouve les vecteurs dont la similarité à la requête tombe dans une bande `[min, max]`.
+    /// 
+    /// Utile pour chercher des vecteurs proches sans être identiques : une borne haute inférieure à
+    /// `1.0` écarte la requête elle-même et ses quasi-doublons.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `min` - La similarité cosinus minimale, incluse.
+    /// * `max` - La similarité cosinus maximale, incluse.
+    /// * `n` - Le nombre maximal de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne au plus `n` tuples `(Uuid, f32)` vérifiant `min <= score <= max`, triés comme ceux de
+    /// `trouver_les_plus_similaires`.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `min > max`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let voisins = db.trouver_dans_bande(&query, 0.7, 0.98, 10);
+    /// ```
+    fn trouver_dans_bande(&self, query: &[f32], min: f32, max: f32, n: usize) -> Vec<(Uuid, f32)> {
+        assert!(min <= max, "la borne basse de la bande doit être inférieure à la borne haute");
+
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
+            .filter(|(_, score)| (min..=max).contains(score))
+            .collect();
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Calcule des points régulièrement espacés sur le segment entre deux vecteurs stockés.
+    /// 
+    /// Le point d'indice `i` vaut `(1 - t) · a + t · b` avec `t = i / (steps - 1)` : le premier point
+    /// est exactement `a` et le dernier exactement `b`. Chaque point peut ensuite servir de requête
+    /// pour observer l'évolution des plus proches voisins le long du chemin.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `a` - L'UUID du vecteur de départ.
+    /// * `b` - L'UUID du vecteur d'arrivée.
+    /// * `steps` - Le nombre de points à produire, extrémités comprises (`1` ne produit que `a`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `steps` points, ou `None` si l'un des deux UUIDs est absent.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les deux vecteurs n'ont pas la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for point in db.interpoler(&a, &b, 11).expect("UUIDs présents") {
+    ///     println!("{:?}", db.trouver_les_plus_similaires(&point, 1));
+    /// }
+    /// ```
+    fn interpoler(&self, a: &Uuid, b: &Uuid, steps: usize) -> Option<Vec<Vec<f32>>> {
+        let depart = self.inner.get(a)?;
+        let arrivee = self.inner.get(b)?;
+        assert_eq!(
+            depart.len(),
+            arrivee.len(),
+            "les vecteurs à interpoler doivent avoir la même dimension"
+        );
+
+        let points = (0..steps)
+            .map(|i| {
+                if i == 0 {
+                    return depart.to_vec();
+                }
+                if i == steps - 1 {
+                    return arrivee.to_vec();
+                }
+                let t = i as f32 / (steps - 1) as f32;
+                depart.iter().zip(arrivee).map(|(x, y)| (1.0 - t) * x + t * y).collect()
+            })
+            .collect();
+        Some(points)
+    }
+
+    /// Calcule l'entropie de Shannon de la distribution des similarités d'une requête.
+    /// 
+    /// Les similarités cosinus `sᵢ` de tous les vecteurs stockés sont transformées en probabilités par
+    /// un softmax `pᵢ = exp(sᵢ / T) / Σ exp(sⱼ / T)`, dont on calcule l'entropie `-Σ pᵢ ln pᵢ` (en nats).
+    /// Une entropie faible signale une requête qui désigne clairement quelques vecteurs ; une entropie
+    /// proche de `ln N` signale au contraire qu'aucun vecteur ne se détache.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `temperature` - La température `T` du softmax : plus elle est basse, plus les écarts de
+    ///   similarité sont accentués.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne l'entropie, comprise entre `0` et `ln N` (`0.0` pour une base vide).
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `temperature` n'est pas strictement positive.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if db.entropie_requete(&query, 0.05) > 2.0 {
+    ///     // Aucune correspondance nette : basculer sur le système secondaire
+    /// }
+    /// ```
+    fn entropie_requete(&self, query: &[f32], temperature: f32) -> f32 {
+        assert!(temperature > 0.0, "la température doit être strictement positive");
+
+        let logits: Vec<f32> = self
+            .inner
+            .iter()
+            .map(|(_, embedding)| calcul_similarite(query, embedding) / temperature)
+            .collect();
+        let Some(maximum) = logits.iter().copied().reduce(f32::max) else {
+            return 0.0;
+        };
+
+        // Soustraire le maximum évite le dépassement de capacité de l'exponentielle
+        let poids: Vec<f32> = logits.iter().map(|l| (l - maximum).exp()).collect();
+        let total: f32 = poids.iter().sum();
+        let somme: f32 = poids
+            .iter()
+            .map(|w| w / total)
+            .filter(|&p| p > 0.0)
+            .map(|p| p * p.ln())
+            .sum();
+        0.0 - somme // Plutôt que `-somme`, qui donnerait `-0.0` pour une distribution certaine
+    }
+
+    /// Insère un vecteur seulement s'il n'est pas un quasi-doublon d'un vecteur déjà stocké.
+    /// 
+    /// La similarité cosinus du nouveau vecteur est calculée avec chaque vecteur stocké, y compris
+    /// celui qu'un UUID réinséré remplacerait : le coût est donc en `O(N)` par insertion, ce qui
+    /// convient à une ingestion en flux sur une base modeste mais pas à un chargement massif.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID unique à associer au vecteur.
+    /// * `embedding` - Le vecteur d'embarquement à insérer.
+    /// * `seuil` - La similarité à partir de laquelle un vecteur stocké est considéré comme un doublon.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `true` si le vecteur a été inséré, ou `false` (sans modifier la base) si un vecteur
+    /// stocké a une similarité supérieure ou égale à `seuil`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if !db.insert_si_nouveau(Uuid::new_v4(), embedding, 0.99) {
+    ///     println!("doublon ignoré");
+    /// }
+    /// ```
+    fn insert_si_nouveau(&mut self, uuid: Uuid, embedding: Vec<f32>, seuil: f32) -> bool {
+        let doublon = self
+            .inner
+            .iter()
+            .any(|(_, stocke)| calcul_similarite(&embedding, stocke) >= seuil);
+        if doublon {
+            return false;
+        }
+        self.insert(uuid, embedding);
+        true
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête, chacun accompagné du percentile de
+    /// son score parmi toute la base.
+    /// 
+    /// Le percentile d'un score `s` est la proportion des vecteurs stockés dont la similarité à la
+    /// requête est inférieure ou égale à `s` : le meilleur résultat a donc toujours un percentile de
+    /// `1.0`. Il situe un score dont la valeur absolue est difficile à interpréter.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs triplets `(Uuid, score, percentile)`, triés comme les résultats de
+    /// `trouver_les_plus_similaires`, avec un percentile dans `]0, 1]`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (uuid, score, percentile) in db.trouver_avec_percentile(&query, 5) {
+    ///     println!("{} : {:.3} (meilleur que {:.0} % de la base)", uuid, score, 100.0 * percentile);
+    /// }
+    /// ```
+    fn trouver_avec_percentile(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32, f32)> {
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
+            .collect();
+        trier_par_score(&mut scores);
+
+        let total = scores.len();
+        let resultats: Vec<(Uuid, f32, f32)> = scores
+            .iter()
+            .take(n)
+            .map(|&(uuid, score)| {
+                let superieurs = scores.partition_point(|&(_, autre)| autre > score);
+                (uuid, score, (total - superieurs) as f32 / total as f32)
+            })
+            .collect();
+        self.compter_consultations(resultats.iter().map(|(uuid, _, _)| uuid));
+        resultats
+    }
+
+    /// Trouve les vecteurs semblables à un exemple positif et dissemblables à un exemple négatif.
+    /// 
+    /// Chaque vecteur `x` reçoit le score `cos(x, pos) - lambda · cos(x, neg)` : un vecteur très
+    /// proche de l'exemple positif recule dans le classement s'il est aussi proche de l'exemple
+    /// négatif (« comme A, mais pas comme B »).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `pos` - L'exemple positif.
+    /// * `neg` - L'exemple négatif.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `lambda` - Le poids donné à la pénalité négative (`0.0` revient à une recherche simple sur `pos`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, score)` triés par score décroissant, puis par UUID.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `pos` et `neg` n'ont pas la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.trouver_positif_negatif(&roi, &homme, 10, 0.5);
+    /// ```
+    fn trouver_positif_negatif(&self, pos: &[f32], neg: &[f32], n: usize, lambda: f32) -> Vec<(Uuid, f32)> {
+        assert_eq!(
+            pos.len(),
+            neg.len(),
+            "les exemples positif et négatif doivent avoir la même dimension"
+        );
+
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                let score = calcul_similarite(pos, embedding) - lambda * calcul_similarite(neg, embedding);
+                (*uuid, score)
+            })
+            .collect();
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Classe une requête selon le centroïde étiqueté le plus proche.
+    /// 
+    /// Les centroïdes peuvent provenir de `kmeans` ou être calculés par classe sur des données
+    /// étiquetées : la base devient alors un classifieur par plus proche centroïde.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur à classer.
+    /// * `centroides` - Les paires `(étiquette, centroïde)`.
+    /// * `metric` - La mesure utilisée, via `Metric::distance`.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne l'étiquette du centroïde de plus faible distance (le premier en cas d'égalité), ou
+    /// `None` si `centroides` est vide.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let centroides = vec![
+    ///     ("sport".to_string(), centre_sport),
+    ///     ("cuisine".to_string(), centre_cuisine),
+    /// ];
+    /// let classe = db.classer_par_centroide(&query, &centroides, Metric::Cosine);
+    /// ```
+    fn classer_par_centroide(&self, query: &[f32], centroides: &[(String, Vec<f32>)], metric: Metric) -> Option<String> {
+        centroides
+            .iter()
+            .map(|(etiquette, centre)| (etiquette, metric.distance(query, centre)))
+            .fold(None, |meilleur: Option<(&String, f32)>, (etiquette, distance)| match meilleur {
+                Some((_, plus_faible)) if plus_faible <= distance => meilleur,
+                _ => Some((etiquette, distance)),
+            })
+            .map(|(etiquette, _)| etiquette.clone())
+    }
+
+    /// Projette les vecteurs stockés dans le plan par t-SNE, pour les visualiser.
+    /// 
+    /// Implémentation exacte (en `O(N²)` par itération, réservée aux bases de quelques milliers de
+    /// vecteurs au plus) :
+    /// 
+    /// 1. pour chaque vecteur `i`, une gaussienne sur les distances euclidiennes au carré définit les
+    ///    affinités `p(j|i)`, sa largeur étant ajustée par dichotomie pour que la perplexité de la
+    ///    distribution vaille `perplexity` ;
+    /// 2. les affinités sont symétrisées : `pᵢⱼ = (p(j|i) + p(i|j)) / 2N` ;
+    /// 3. des positions planes, tirées près de l'origine, sont déplacées par descente de gradient
+    ///    (avec inertie et gains adaptatifs) pour que les affinités de Student `qᵢⱼ ∝ 1 / (1 + ‖yᵢ - yⱼ‖²)`
+    ///    reproduisent les `pᵢⱼ`, les `pᵢⱼ` étant multipliés par 4 pendant le premier quart des
+    ///    itérations (« exagération précoce ») pour bien séparer les groupes.
+    /// 
+    /// Seules les relations de voisinage ont un sens dans le résultat : les distances entre groupes et
+    /// la taille des groupes ne sont pas fidèles.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `perplexity` - Le nombre effectif de voisins de chaque point, typiquement entre 5 et 50, et
+    ///   inférieur au nombre de vecteurs.
+    /// * `iters` - Le nombre d'itérations de la descente de gradient.
+    /// * `seed` - La graine des positions initiales.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la position plane de chaque vecteur, triée par UUID.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `perplexity` n'est pas strictement positive ou si les vecteurs stockés n'ont pas
+    /// tous la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (uuid, [x, y]) in db.projeter_2d(30.0, 1000, 42) {
+    ///     println!("{},{},{}", uuid, x, y);
+    /// }
+    /// ```
+    fn projeter_2d(&self, perplexity: f32, iters: usize, seed: u64) -> Vec<(Uuid, [f32; 2])> {
+        assert!(perplexity > 0.0, "la perplexité doit être strictement positive");
+
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        let nombre = entrees.len();
+        let dimension = entrees.first().map_or(0, |(_, embedding)| embedding.len());
+        assert!(
+            entrees.iter().all(|(_, embedding)| embedding.len() == dimension),
+            "la projection exige des vecteurs de même dimension"
+        );
+        if nombre < 2 {
+            return entrees.iter().map(|(uuid, _)| (**uuid, [0.0, 0.0])).collect();
+        }
+
+        // Distances euclidiennes au carré entre vecteurs d'origine
+        let mut distances = vec![0.0f64; nombre * nombre];
+        for i in 0..nombre {
+            for j in (i + 1)..nombre {
+                let d = f64::from(distance_euclidienne(entrees[i].1, entrees[j].1));
+                distances[i * nombre + j] = d * d;
+                distances[j * nombre + i] = d * d;
+            }
+        }
+
+        // Affinités conditionnelles p(j|i), de perplexité cible, par dichotomie sur β = 1 / 2σ²
+        let entropie_cible = f64::from(perplexity).ln();
+        let mut p = vec![0.0f64; nombre * nombre];
+        for i in 0..nombre {
+            let ligne = &distances[i * nombre..(i + 1) * nombre];
+            let minimum = (0..nombre).filter(|&j| j != i).map(|j| ligne[j]).fold(f64::INFINITY, f64::min);
+            let (mut beta, mut beta_min, mut beta_max) = (1.0f64, 0.0f64, f64::INFINITY);
+            for _ in 0..64 {
+                let mut somme = 0.0;
+                let mut somme_ponderee = 0.0;
+                for j in (0..nombre).filter(|&j| j != i) {
+                    // Décaler par la distance minimale évite que toutes les exponentielles s'annulent
+                    let poids = (-beta * (ligne[j] - minimum)).exp();
+                    p[i * nombre + j] = poids;
+                    somme += poids;
+                    somme_ponderee += poids * (ligne[j] - minimum);
+                }
+                for j in (0..nombre).filter(|&j| j != i) {
+                    p[i * nombre + j] /= somme;
+                }
+                let entropie = somme.ln() + beta * somme_ponderee / somme;
+                let ecart = entropie - entropie_cible;
+                if ecart.abs() < 1e-5 {
+                    break;
+                }
+                if ecart > 0.0 {
+                    beta_min = beta;
+                    beta = if beta_max.is_infinite() { beta * 2.0 } else { (beta + beta_max) / 2.0 };
+                } else {
+                    beta_max = beta;
+                    beta = (beta + beta_min) / 2.0;
+                }
+            }
+        }
+
+        // Symétrisation : pᵢⱼ = (p(j|i) + p(i|j)) / 2N
+        let mut pij = vec![0.0f64; nombre * nombre];
+        for i in 0..nombre {
+            for j in 0..nombre {
+                if i != j {
+                    pij[i * nombre + j] = ((p[i * nombre + j] + p[j * nombre + i]) / (2.0 * nombre as f64)).max(1e-12);
+                }
+            }
+        }
+
+        // Descente de gradient sur les positions planes, avec un pas qui croît avec le nombre de points
+        let taux = (nombre as f64 / 12.0).max(50.0);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut positions: Vec<[f64; 2]> = (0..nombre)
+            .map(|_| [rng.gen_range(-1e-2..1e-2), rng.gen_range(-1e-2..1e-2)])
+            .collect();
+        let mut vitesses = vec![[0.0f64; 2]; nombre];
+        let mut gains = vec![[1.0f64; 2]; nombre];
+        let fin_exageration = iters / 4;
+        let mut noyau = vec![0.0f64; nombre * nombre];
+
+        for iteration in 0..iters {
+            let exageration = if iteration < fin_exageration { 4.0 } else { 1.0 };
+            let inertie = if iteration < fin_exageration { 0.5 } else { 0.8 };
+
+            // Noyau de Student et sa somme, qui normalise les qᵢⱼ
+            let mut somme_noyau = 0.0;
+            for i in 0..nombre {
+                for j in (i + 1)..nombre {
+                    let dx = positions[i][0] - positions[j][0];
+                    let dy = positions[i][1] - positions[j][1];
+                    let k = 1.0 / (1.0 + dx * dx + dy * dy);
+                    noyau[i * nombre + j] = k;
+                    noyau[j * nombre + i] = k;
+                    somme_noyau += 2.0 * k;
+                }
+            }
+
+            for i in 0..nombre {
+                // ∂C/∂yᵢ = 4 Σⱼ (pᵢⱼ - qᵢⱼ) · noyauᵢⱼ · (yᵢ - yⱼ)
+                let mut gradient = [0.0f64; 2];
+                for j in (0..nombre).filter(|&j| j != i) {
+                    let k = noyau[i * nombre + j];
+                    let force = 4.0 * (exageration * pij[i * nombre + j] - k / somme_noyau) * k;
+                    gradient[0] += force * (positions[i][0] - positions[j][0]);
+                    gradient[1] += force * (positions[i][1] - positions[j][1]);
+                }
+                for axe in 0..2 {
+                    // Gains adaptatifs : accélérer tant que le gradient garde la même direction
+                    gains[i][axe] = if (gradient[axe] > 0.0) != (vitesses[i][axe] > 0.0) {
+                        gains[i][axe] + 0.2
+                    } else {
+                        (gains[i][axe] * 0.8).max(0.01)
+                    };
+                    vitesses[i][axe] = inertie * vitesses[i][axe] - taux * gains[i][axe] * gradient[axe];
+                }
+            }
+            for (position, vitesse) in positions.iter_mut().zip(&vitesses) {
+                position[0] += vitesse[0];
+                position[1] += vitesse[1];
+            }
+        }
+
+        entrees
+            .iter()
+            .zip(positions)
+            .map(|((uuid, _), [x, y])| (**uuid, [x as f32, y as f32]))
+            .collect()
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête, avec des scores entiers à virgule fixe.
+    /// 
+    /// Chaque similarité cosinus, ramenée dans `[-1, 1]` pour absorber les erreurs d'arrondi, devient
+    /// `round(cosinus · echelle)` : les scores sont exacts, comparables par égalité et utilisables comme
+    /// clés de hachage. Les résultats sont dans le même ordre que ceux de
+    /// `trouver_les_plus_similaires` ; deux résultats dont les cosinus sont proches peuvent partager
+    /// le même score entier.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// * `echelle` - Le score entier d'une similarité de `1.0` (par exemple `1000` pour trois décimales).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` meilleurs tuples `(Uuid, score)`, avec des scores dans `[-echelle, echelle]`.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `echelle` dépasse `i32::MAX`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let resultats = db.trouver_les_plus_similaires_quantise(&query, 10, 1000);
+    /// ```
+    fn trouver_les_plus_similaires_quantise(&self, query: &[f32], n: usize, echelle: u32) -> Vec<(Uuid, i32)> {
+        assert!(i32::try_from(echelle).is_ok(), "l'échelle doit tenir dans un i32");
+
+        self.trouver_sans_compter(query, n, TieBreak::Uuid)
+            .into_iter()
+            .map(|(uuid, score)| {
+                let quantise = (f64::from(score.clamp(-1.0, 1.0)) * f64::from(echelle)).round() as i32;
+                (uuid, quantise)
+            })
+            .collect()
+    }
+
+    /// Calcule la distance moyenne de chaque vecteur à son plus proche voisin, mesure de densité de
+    /// la base.
+    /// 
+    /// Pour chaque vecteur stocké, la distance (`Metric::distance`) à chacun des autres est calculée
+    /// et la plus faible est retenue ; le résultat est la moyenne de ces distances. Le coût est en
+    /// `O(N²)` : à réserver à un suivi périodique.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `metric` - La mesure de distance utilisée.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la distance moyenne au plus proche voisin, ou `None` si la base contient moins de
+    /// deux vecteurs.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if let Some(densite) = db.distance_moyenne_voisin(Metric::Euclidean) {
+    ///     println!("distance moyenne au plus proche voisin : {:.4}", densite);
+    /// }
+    /// ```
+    fn distance_moyenne_voisin(&self, metric: Metric) -> Option<f32> {
+        let entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        if entrees.len() < 2 {
+            return None;
+        }
+
+        let somme: f64 = entrees
+            .iter()
+            .map(|(uuid, embedding)| {
+                entrees
+                    .iter()
+                    .filter(|(autre, _)| autre != uuid)
+                    .map(|(_, voisin)| metric.distance(embedding, voisin))
+                    .fold(f32::INFINITY, f32::min) as f64
+            })
+            .sum();
+        Some((somme / entrees.len() as f64) as f32)
+    }
+
+    /// Calcule le vecteur d'un texte avec un `Embedder` et l'insère sous un nouvel UUID.
+    /// 
+    /// Le texte lui-même n'est pas conservé ; il peut être rangé dans les métadonnées au besoin.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `text` - Le texte à insérer.
+    /// * `embedder` - Le modèle utilisé pour calculer le vecteur.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne l'UUID généré pour l'entrée.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si le vecteur produit n'a pas la dimension annoncée par `embedder.dimension()`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let embedder = EmbedderHachage { dimension: 256 };
+    /// let uuid = db.insert_text("Ceci est un exemple de phrase", &embedder);
+    /// ```
+    fn insert_text<E: Embedder>(&mut self, text: &str, embedder: &E) -> Uuid {
+        let embedding = embedder.embed(text);
+        assert_eq!(
+            embedding.len(),
+            embedder.dimension(),
+            "l'embedder doit produire des vecteurs de la dimension annoncée"
+        );
+
+        let uuid = Uuid::new_v4();
+        self.insert(uuid, embedding);
+        uuid
+    }
+
+    /// Évalue une recherche contre un ensemble de résultats pertinents connus, pour plusieurs `k`.
+    /// 
+    /// La requête est exécutée une seule fois jusqu'au plus grand `k` ; le nombre de résultats
+    /// pertinents est cumulé le long du classement, ce qui évite de relancer la recherche pour chaque
+    /// valeur de `k`. Pour chaque `k`, la précision vaut `pertinents trouvés / k` et le rappel
+    /// `pertinents trouvés / |pertinents|`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `pertinents` - Les UUIDs jugés pertinents pour la requête.
+    /// * `ks` - Les valeurs de `k` à évaluer, dans un ordre quelconque.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne un triplet `(k, précision, rappel)` par valeur de `ks`, dans le même ordre. La
+    /// précision vaut `0.0` pour `k = 0`, et le rappel vaut `0.0` si `pertinents` est vide.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (k, precision, rappel) in db.evaluer(&query, &pertinents, &[1, 5, 10]) {
+    ///     println!("P@{} = {:.2}, R@{} = {:.2}", k, precision, k, rappel);
+    /// }
+    /// ```
+    fn evaluer(&self, query: &[f32], pertinents: &HashSet<Uuid>, ks: &[usize]) -> Vec<(usize, f32, f32)> {
+        let k_max = ks.iter().copied().max().unwrap_or(0);
+
+        // trouves[i] = nombre de résultats pertinents parmi les i premiers
+        let mut trouves = vec![0usize];
+        for (uuid, _) in self.trouver_sans_compter(query, k_max, TieBreak::Uuid) {
+            let precedent = trouves[trouves.len() - 1];
+            trouves.push(precedent + usize::from(pertinents.contains(&uuid)));
+        }
+
+        ks.iter()
+            .map(|&k| {
+                let trouves = trouves[k.min(trouves.len() - 1)] as f32;
+                let precision = if k == 0 { 0.0 } else { trouves / k as f32 };
+                let rappel = if pertinents.is_empty() { 0.0 } else { trouves / pertinents.len() as f32 };
+                (k, precision, rappel)
+            })
+            .collect()
+    }
+
+    /// Apparie un à un les vecteurs de deux bases en maximisant la similarité totale.
+    /// 
+    /// La matrice des similarités cosinus entre les vecteurs des deux bases est construite, puis le
+    /// problème d'affectation est résolu exactement par l'algorithme hongrois, en `O(n² · m)` pour
+    /// `n ≤ m` vecteurs. Si les bases n'ont pas la même taille, chaque vecteur de la plus petite est
+    /// apparié et les vecteurs en surplus de la plus grande restent sans partenaire.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` - La base à apparier avec celle-ci.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les triplets `(uuid de self, uuid de other, similarité)`, triés par UUID de `self`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// // Retrouver la nouvelle version de chaque document
+    /// for (ancien, nouveau, similarite) in anciens.appariement_optimal(&nouveaux) {
+    ///     println!("{} -> {} ({:.3})", ancien, nouveau, similarite);
+    /// }
+    /// ```
+    fn appariement_optimal<T: VectorStore>(&self, other: &Db<T>) -> Vec<(Uuid, Uuid, f32)> {
+        let mut gauche: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        gauche.sort_by_key(|(uuid, _)| **uuid);
+        let mut droite: Vec<(&Uuid, &[f32])> = other.inner.iter().collect();
+        droite.sort_by_key(|(uuid, _)| **uuid);
+        if gauche.is_empty() || droite.is_empty() {
+            return Vec::new();
+        }
+
+        // L'algorithme affecte les lignes : placer en lignes la plus petite des deux bases
+        let transposer = gauche.len() > droite.len();
+        let (lignes, colonnes) = if transposer { (&droite, &gauche) } else { (&gauche, &droite) };
+        let similarites: Vec<Vec<f32>> = lignes
+            .iter()
+            .map(|(_, a)| colonnes.iter().map(|(_, b)| calcul_similarite(a, b)).collect())
+            .collect();
+        let couts: Vec<Vec<f64>> = similarites
+            .iter()
+            .map(|ligne| ligne.iter().map(|&s| -f64::from(s)).collect())
+            .collect();
+
+        let mut appariement: Vec<(Uuid, Uuid, f32)> = affectation_hongroise(&couts)
+            .into_iter()
+            .enumerate()
+            .map(|(i, j)| {
+                let (l, c) = (*lignes[i].0, *colonnes[j].0);
+                let (a, b) = if transposer { (c, l) } else { (l, c) };
+                (a, b, similarites[i][j])
+            })
+            .collect();
+        appariement.sort_by_key(|(uuid, _, _)| *uuid);
+        appariement
+    }
+
+    /// Calcule la moyenne et la matrice de covariance (de population) des vecteurs stockés, en `f64`.
+    /// 
+    /// Si le suivi de la covariance est actif (voir `avec_covariance`), la valeur tenue à jour est
+    /// retournée directement, en `O(D²)` ; sinon, elle est calculée en parcourant toute la base, en
+    /// `O(N · D²)`.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `(moyenne, covariance)`, la covariance étant donnée ligne par ligne, ou `None` si la
+    /// base est vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Sans suivi actif, panique si les vecteurs stockés n'ont pas tous la même dimension.
+    fn matrice_covariance(&self) -> Option<(Vec<f64>, Vec<Vec<f64>>)> {
+        if let Some(covariance) = &self.covariance {
+            return covariance.moyenne_et_covariance();
+        }
+
+        let moyenne: Vec<f64> = self.centroide()?.into_iter().map(f64::from).collect();
+        let dimension = moyenne.len();
+
+        let mut covariance = vec![vec![0.0f64; dimension]; dimension];
+        let mut nombre = 0usize;
+        let mut centre = vec![0.0f64; dimension];
+        for (_, embedding) in self.inner.iter() {
+            assert_eq!(embedding.len(), dimension, "la covariance exige des vecteurs de même dimension");
+            for ((c, &x), m) in centre.iter_mut().zip(embedding).zip(&moyenne) {
+                *c = f64::from(x) - m;
+            }
+            for (ligne, &ci) in covariance.iter_mut().zip(&centre) {
+                for (case, &cj) in ligne.iter_mut().zip(&centre) {
+                    *case += ci * cj;
+                }
+            }
+            nombre += 1;
+        }
+        for case in covariance.iter_mut().flatten() {
+            *case /= nombre as f64;
+        }
+        Some((moyenne, covariance))
+    }
+
+    /// Calcule la part cumulée de la variance expliquée par les premières composantes principales.
+    /// 
+    /// Les plus grandes valeurs propres de la matrice de covariance sont obtenues une à une par la
+    /// méthode de la puissance itérée, chaque composante trouvée étant retirée de la matrice
+    /// (déflation) avant de chercher la suivante. La valeur `i` du résultat est la somme des `i + 1`
+    /// plus grandes valeurs propres divisée par la variance totale (la trace de la covariance) : on lit
+    /// directement combien de composantes garder pour conserver, par exemple, 95 % de la variance.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `max_components` - Le nombre de composantes à évaluer (limité à la dimension).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les parts cumulées, croissantes et dans `[0, 1]`, ou un vecteur vide si la base est
+    /// vide ou sans variance.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs stockés n'ont pas tous la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let parts = db.variance_expliquee(50);
+    /// let composantes = parts.iter().position(|&p| p >= 0.95).map(|i| i + 1);
+    /// ```
+    fn variance_expliquee(&self, max_components: usize) -> Vec<f32> {
+        // Nombre maximal d'itérations et tolérance de convergence de la puissance itérée
+        const ITERATIONS: usize = 1000;
+        const TOLERANCE: f64 = 1e-12;
+
+        let Some((_, mut covariance)) = self.matrice_covariance() else {
+            return Vec::new();
+        };
+        let dimension = covariance.len();
+        let totale: f64 = (0..dimension).map(|i| covariance[i][i]).sum();
+        if totale <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut parts = Vec::with_capacity(max_components.min(dimension));
+        let mut cumul = 0.0f64;
+        for _ in 0..max_components.min(dimension) {
+            // Vecteur de départ fixe, non orthogonal en pratique aux composantes recherchées
+            let mut vecteur: Vec<f64> = (0..dimension).map(|i| 1.0 + i as f64 / dimension as f64).collect();
+            let mut valeur = 0.0f64;
+            for _ in 0..ITERATIONS {
+                let image: Vec<f64> = covariance
+                    .iter()
+                    .map(|ligne| ligne.iter().zip(&vecteur).map(|(a, b)| a * b).sum())
+                    .collect();
+                let longueur = image.iter().map(|x| x * x).sum::<f64>().sqrt();
+                if longueur == 0.0 {
+                    valeur = 0.0;
+                    break;
+                }
+                let suivant: Vec<f64> = image.iter().map(|x| x / longueur).collect();
+                let deplacement: f64 = suivant.iter().zip(&vecteur).map(|(a, b)| (a - b) * (a - b)).sum();
+                vecteur = suivant;
+                valeur = longueur;
+                if deplacement < TOLERANCE {
+                    break;
+                }
+            }
+
+            // Déflation : retirer la composante trouvée
+            for (i, ligne) in covariance.iter_mut().enumerate() {
+                for (j, case) in ligne.iter_mut().enumerate() {
+                    *case -= valeur * vecteur[i] * vecteur[j];
+                }
+            }
+            cumul += valeur;
+            parts.push((cumul / totale).min(1.0) as f32);
+        }
+        parts
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête en répartissant le calcul des
+    /// similarités sur le pool de threads rayon.
+    /// 
+    /// Le résultat est identique à celui de `trouver_les_plus_similaires`. Le premier appel paie
+    /// l'initialisation du pool, sauf si `prechauffer` a été appelé auparavant.
+    /// 
+    /// Disponible uniquement avec la fonctionnalité `rayon`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    #[cfg(feature = "rayon")]
+    fn trouver_les_plus_similaires_parallele(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)>
+    where
+        S: Sync,
+    {
+        use rayon::prelude::*;
+
+        let entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        let mut scores: Vec<(Uuid, f32)> = entrees
+            .par_iter()
+            .map(|(uuid, embedding)| (**uuid, calcul_similarite(query, embedding)))
+            .collect();
+        trier_par_score(&mut scores);
+        scores.truncate(n);
+        self.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+        scores
+    }
+
+    /// Initialise à l'avance le pool de threads des recherches parallèles.
+    /// 
+    /// rayon ne démarre ses threads qu'au premier travail parallèle, ce qui fausse la latence de la
+    /// première requête. Cette méthode exécute un travail vide sur chacun des threads du pool pour
+    /// que les requêtes suivantes aient une latence stable. Elle n'a d'intérêt qu'avec la
+    /// fonctionnalité `rayon` ; sans elle, elle ne fait rien.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// db.prechauffer();
+    /// let debut = std::time::Instant::now();
+    /// let resultats = db.trouver_les_plus_similaires_parallele(&query, 10);
+    /// println!("latence : {:?}", debut.elapsed());
+    /// ```
+    fn prechauffer(&self) {
+        #[cfg(feature = "rayon")]
+        rayon::broadcast(|_| ());
+    }
+
+    /// Calcule le centroïde (vecteur moyen) de chaque groupe de métadonnées.
+    /// 
+    /// Le groupe de chaque entrée est calculé par `key_fn` à partir de ses métadonnées, comme dans
+    /// `trouver_groupe_par` ; les entrées sans métadonnées sont ignorées. Les centroïdes obtenus
+    /// servent de prototypes : chercher parmi eux (ou les passer à `classer_par_centroide`) est bien
+    /// plus rapide que parcourir tous les vecteurs.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `key_fn` - La fonction qui associe un groupe aux métadonnées d'une entrée.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le vecteur moyen de chaque groupe non vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs d'un même groupe n'ont pas tous la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let prototypes = db.centroides_par_groupe(|m| m.get("categorie").cloned());
+    /// ```
+    fn centroides_par_groupe<K, F>(&self, key_fn: F) -> HashMap<K, Vec<f32>>
+    where
+        K: Eq + Hash,
+        F: Fn(&Metadonnees) -> K,
+    {
+        self.centroides_par_groupe_pondere(key_fn, |_, _| 1.0)
+    }
+
+    /// Calcule le centroïde pondéré de chaque groupe de métadonnées.
+    /// 
+    /// Comme `centroides_par_groupe`, mais chaque entrée contribue à la moyenne de son groupe en
+    /// proportion de son poids : `Σ wᵢ xᵢ / Σ wᵢ`. Les poids peuvent refléter la popularité, la
+    /// fraîcheur ou la confiance de chaque entrée. Avec des poids tous égaux, on retrouve
+    /// `centroides_par_groupe`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `key_fn` - La fonction qui associe un groupe aux métadonnées d'une entrée.
+    /// * `poids_fn` - La fonction qui associe un poids positif ou nul à une entrée.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le vecteur moyen pondéré de chaque groupe dont le poids total est strictement
+    /// positif.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si un poids est négatif ou non fini, ou si les vecteurs d'un même groupe n'ont pas tous
+    /// la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let prototypes = db.centroides_par_groupe_pondere(
+    ///     |m| m.get("categorie").cloned(),
+    ///     |_, m| m.get("vues").and_then(|v| v.parse().ok()).unwrap_or(1.0),
+    /// );
+    /// ```
+    fn centroides_par_groupe_pondere<K, F, W>(&self, key_fn: F, poids_fn: W) -> HashMap<K, Vec<f32>>
+    where
+        K: Eq + Hash,
+        F: Fn(&Metadonnees) -> K,
+        W: Fn(&Uuid, &Metadonnees) -> f32,
+    {
+        // Somme pondérée des vecteurs et poids total de chaque groupe
+        let mut sommes: HashMap<K, (Vec<f64>, f64)> = HashMap::new();
+        for (uuid, metadonnees) in &self.metadonnees {
+            let Some(embedding) = self.inner.get(uuid) else {
+                continue;
+            };
+            let poids = poids_fn(uuid, metadonnees);
+            assert!(
+                poids.is_finite() && poids >= 0.0,
+                "les poids doivent être finis et positifs ou nuls"
+            );
+
+            let (somme, total) = sommes.entry(key_fn(metadonnees)).or_insert_with(|| (Vec::new(), 0.0));
+            if somme.is_empty() {
+                *somme = vec![0.0; embedding.len()];
+            }
+            assert_eq!(
+                somme.len(),
+                embedding.len(),
+                "les vecteurs d'un même groupe doivent avoir la même dimension"
+            );
+            for (s, x) in somme.iter_mut().zip(embedding) {
+                *s += f64::from(poids) * f64::from(*x);
+            }
+            *total += f64::from(poids);
+        }
+
+        sommes
+            .into_iter()
+            .filter(|(_, (_, total))| *total > 0.0)
+            .map(|(groupe, (somme, total))| (groupe, somme.iter().map(|s| (s / total) as f32).collect()))
+            .collect()
+    }
+
+    /// Calcule le facteur d'anomalie local (LOF) de chaque vecteur stocké.
+    /// 
+    /// Pour chaque vecteur `a`, on détermine ses `k` plus proches voisins `N(a)` (distance
+    /// euclidienne, égalités départagées par UUID) et sa `k`-distance, la distance à son `k`-ième
+    /// voisin. La distance d'atteignabilité de `a` à `b` vaut `max(k-distance(b), d(a, b))` ; la densité
+    /// locale de `a` est l'inverse de sa distance d'atteignabilité moyenne à ses voisins. Le facteur de
+    /// `a` est enfin la densité moyenne de ses voisins divisée par sa propre densité : autour de `1`
+    /// pour un point aussi dense que son voisinage, nettement au-dessus pour un point isolé, même à
+    /// proximité d'un groupe lâche. Comme dans l'implémentation de scikit-learn, `1e-10` est ajouté
+    /// aux distances moyennes pour que des doublons exacts ne donnent pas de densité infinie.
+    /// 
+    /// Le coût est en `O(N²)`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `k` - Le nombre de voisins considérés, limité à `N - 1`.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne le facteur de chaque UUID, ou une table vide si la base contient moins de deux vecteurs.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `k` vaut zéro.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let aberrants: Vec<Uuid> = db
+    ///     .facteur_aberrant_local(10)
+    ///     .into_iter()
+    ///     .filter(|(_, facteur)| *facteur > 1.5)
+    ///     .map(|(uuid, _)| uuid)
+    ///     .collect();
+    /// ```
+    fn facteur_aberrant_local(&self, k: usize) -> HashMap<Uuid, f32> {
+        assert!(k > 0, "il faut au moins un voisin");
+
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        let nombre = entrees.len();
+        if nombre < 2 {
+            return HashMap::new();
+        }
+        let k = k.min(nombre - 1);
+
+        // k plus proches voisins de chaque point, sous forme (indice, distance)
+        let voisins: Vec<Vec<(usize, f64)>> = (0..nombre)
+            .map(|i| {
+                let mut distances: Vec<(usize, f64)> = (0..nombre)
+                    .filter(|&j| j != i)
+                    .map(|j| (j, f64::from(distance_euclidienne(entrees[i].1, entrees[j].1))))
+                    .collect();
+                distances.sort_by(|a, b| {
+                    a.1.partial_cmp(&b.1)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then(a.0.cmp(&b.0))
+                });
+                distances.truncate(k);
+                distances
+            })
+            .collect();
+        let k_distances: Vec<f64> = voisins.iter().map(|v| v[k - 1].1).collect();
+
+        let densites: Vec<f64> = voisins
+            .iter()
+            .map(|v| {
+                let atteignabilite: f64 = v.iter().map(|&(j, d)| d.max(k_distances[j])).sum::<f64>() / k as f64;
+                1.0 / (atteignabilite + 1e-10)
+            })
+            .collect();
+
+        entrees
+            .iter()
+            .enumerate()
+            .map(|(i, (uuid, _))| {
+                let densite_voisins: f64 = voisins[i].iter().map(|&(j, _)| densites[j]).sum::<f64>() / k as f64;
+                (**uuid, (densite_voisins / densites[i]) as f32)
+            })
+            .collect()
+    }
+
+    /// Trouve les `n` vecteurs les plus similaires à la requête, indexés par leur rang.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne une table ordonnée du rang (à partir de `0`) vers le tuple `(Uuid, f32)` classé à ce
+    /// rang par `trouver_les_plus_similaires`, départage des égalités compris.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let classement = db.trouver_classe(&query, 10);
+    /// if let Some((uuid, score)) = classement.get(&3) {
+    ///     println!("rang 3 : {} ({:.3})", uuid, score);
+    /// }
+    /// ```
+    fn trouver_classe(&self, query: &[f32], n: usize) -> BTreeMap<usize, (Uuid, f32)> {
+        self.trouver_sans_compter(query, n, TieBreak::Uuid)
+            .into_iter()
+            .enumerate()
+            .collect()
+    }
+
+    /// Calcule l'inverse de la matrice de covariance, régularisée, des vecteurs stockés.
+    /// 
+    /// Une covariance estimée sur moins de vecteurs que de dimensions est singulière ; on lui ajoute
+    /// donc `λ · I` avec `λ = 1e-6 · trace / D` (ou `1e-6` si la trace est nulle) avant de l'inverser.
+    fn calculer_inverse_covariance(&self) -> Option<Vec<Vec<f64>>> {
+        let (_, mut covariance) = self.matrice_covariance()?;
+        let dimension = covariance.len();
+        let trace: f64 = (0..dimension).map(|i| covariance[i][i]).sum();
+        let regularisation = if trace > 0.0 { 1e-6 * trace / dimension as f64 } else { 1e-6 };
+        for (i, ligne) in covariance.iter_mut().enumerate() {
+            ligne[i] += regularisation;
+        }
+        inverser_matrice(&covariance)
+    }
+
+    /// Prépare les recherches par distance de Mahalanobis en calculant et en mémorisant l'inverse de
+    /// la matrice de covariance des vecteurs stockés.
+    /// 
+    /// L'inverse mémorisé est oublié dès que la base est modifiée (insertion, suppression,
+    /// transformation) : il faut alors rappeler cette méthode, sans quoi `trouver_mahalanobis` le
+    /// recalcule à chaque requête. Le calcul coûte `O(N · D² + D³)`, ou `O(D³)` si le suivi de la
+    /// covariance est actif (voir `avec_covariance`) : seule l'inversion reste alors à faire.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les vecteurs stockés n'ont pas tous la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// db.distance_mahalanobis();
+    /// let resultats = db.trouver_mahalanobis(&query, 10);
+    /// ```
+    fn distance_mahalanobis(&mut self) {
+        self.inverse_covariance = self.calculer_inverse_covariance();
+    }
+
+    /// Trouve les `n` vecteurs les plus proches de la requête selon la distance de Mahalanobis.
+    /// 
+    /// La distance `√((x - q)ᵀ Σ⁻¹ (x - q))`, où `Σ` est la covariance des vecteurs stockés, tient
+    /// compte des corrélations entre dimensions : dans un espace anisotrope, un écart le long d'une
+    /// direction de forte variance pèse moins qu'un écart de même longueur dans une direction où les
+    /// vecteurs varient peu. L'inverse de la covariance mémorisé par `distance_mahalanobis` est
+    /// utilisé s'il est à jour ; sinon il est recalculé pour cette seule requête.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `n` - Le nombre de résultats à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `n` tuples `(Uuid, distance)` les plus proches, triés par distance croissante puis
+    /// par UUID (vide si la base est vide).
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si la requête n'a pas la dimension des vecteurs stockés.
+    fn trouver_mahalanobis(&self, query: &[f32], n: usize) -> Vec<(Uuid, f32)> {
+        let inverse = match &self.inverse_covariance {
+            Some(inverse) => std::borrow::Cow::Borrowed(inverse),
+            None => match self.calculer_inverse_covariance() {
+                Some(inverse) => std::borrow::Cow::Owned(inverse),
+                None => return Vec::new(),
+            },
+        };
+        assert_eq!(
+            query.len(),
+            inverse.len(),
+            "la requête doit avoir la dimension des vecteurs stockés"
+        );
+
+        let mut distances: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| {
+                let ecart: Vec<f64> = embedding
+                    .iter()
+                    .zip(query)
+                    .map(|(&x, &q)| f64::from(x) - f64::from(q))
+                    .collect();
+                let forme: f64 = inverse
+                    .iter()
+                    .zip(&ecart)
+                    .map(|(ligne, ei)| ei * ligne.iter().zip(&ecart).map(|(a, ej)| a * ej).sum::<f64>())
+                    .sum();
+                (*uuid, forme.max(0.0).sqrt() as f32)
+            })
+            .collect();
+        distances.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
+        distances.truncate(n);
+        self.compter_consultations(distances.iter().map(|(uuid, _)| uuid));
+        distances
+    }
+
+    /// Calcule l'arbre couvrant minimal du graphe complet des distances entre vecteurs stockés.
+    /// 
+    /// L'algorithme de Prim part du plus petit UUID et ajoute à chaque étape le sommet le plus proche
+    /// de l'arbre en construction, en `O(N²)` sans matrice de distances précalculée. Retirer les
+    /// arêtes les plus longues de l'arbre donne les groupes d'un regroupement hiérarchique à lien
+    /// simple ; trier les arêtes par distance donne directement son dendrogramme.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `metric` - La mesure de distance utilisée (`Metric::distance`).
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les `N - 1` arêtes `(uuid déjà dans l'arbre, uuid ajouté, distance)`, dans leur ordre
+    /// d'ajout (vide si la base contient moins de deux vecteurs).
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let mut aretes = db.arbre_couvrant_minimal(Metric::Cosine);
+    /// aretes.sort_by(|a, b| a.2.total_cmp(&b.2));
+    /// ```
+    fn arbre_couvrant_minimal(&self, metric: Metric) -> Vec<(Uuid, Uuid, f32)> {
+        let mut entrees: Vec<(&Uuid, &[f32])> = self.inner.iter().collect();
+        entrees.sort_by_key(|(uuid, _)| **uuid);
+        let nombre = entrees.len();
+        if nombre < 2 {
+            return Vec::new();
+        }
+
+        // Pour chaque sommet hors de l'arbre : distance à l'arbre et sommet de l'arbre le plus proche
+        let mut dans_arbre = vec![false; nombre];
+        let mut plus_proche: Vec<(f32, usize)> = vec![(f32::INFINITY, 0); nombre];
+        let mut courant = 0;
+        dans_arbre[0] = true;
+
+        let mut aretes = Vec::with_capacity(nombre - 1);
+        for _ in 1..nombre {
+            for (j, (_, embedding)) in entrees.iter().enumerate() {
+                if !dans_arbre[j] {
+                    let distance = metric.distance(entrees[courant].1, embedding);
+                    if distance < plus_proche[j].0 {
+                        plus_proche[j] = (distance, courant);
+                    }
+                }
+            }
+
+            let suivant = (0..nombre)
+                .filter(|&j| !dans_arbre[j])
+                .min_by(|&a, &b| {
+                    plus_proche[a]
+                        .0
+                        .partial_cmp(&plus_proche[b].0)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .expect("il reste des sommets hors de l'arbre");
+            let (distance, parent) = plus_proche[suivant];
+            aretes.push((*entrees[parent].0, *entrees[suivant].0, distance));
+            dans_arbre[suivant] = true;
+            courant = suivant;
+        }
+        aretes
+    }
+
+    /// Répartit les résultats d'une requête en paliers de similarité.
+    /// 
+    /// `paliers` donne les seuils bas des paliers, du plus exigeant au moins exigeant : avec
+    /// `[0.8, 0.5, 0.2]`, le premier palier reçoit les scores `s >= 0.8`, le deuxième `0.5 <= s < 0.8`
+    /// et le troisième `0.2 <= s < 0.5`. Un score égal à un seuil appartient donc toujours au palier
+    /// que ce seuil ouvre. Les résultats sous le dernier seuil sont écartés.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `paliers` - Les seuils bas des paliers, strictement décroissants.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne un vecteur de résultats par palier, dans l'ordre de `paliers`, chacun trié comme les
+    /// résultats de `trouver_les_plus_similaires`.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si les seuils ne sont pas strictement décroissants.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// let paliers = db.trouver_par_paliers(&query, &[0.8, 0.5, 0.2]);
+    /// let (forts, possibles, faibles) = (&paliers[0], &paliers[1], &paliers[2]);
+    /// ```
+    fn trouver_par_paliers(&self, query: &[f32], paliers: &[f32]) -> Vec<Vec<(Uuid, f32)>> {
+        assert!(
+            paliers.windows(2).all(|paire| paire[0] > paire[1]),
+            "les seuils des paliers doivent être strictement décroissants"
+        );
+
+        let mut scores: Vec<(Uuid, f32)> = self
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
+            .collect();
+        trier_par_score(&mut scores);
+
+        let mut resultats = vec![Vec::new(); paliers.len()];
+        for (uuid, score) in scores {
+            if let Some(palier) = paliers.iter().position(|&seuil| score >= seuil) {
+                resultats[palier].push((uuid, score));
+            }
+        }
+        self.compter_consultations(resultats.iter().flatten().map(|(uuid, _)| uuid));
+        resultats
+    }
+
+    /// Calcule la moyenne et l'écart type (de population) de chaque dimension des vecteurs stockés.
+    /// 
+    /// Si le suivi des statistiques est actif (voir `avec_statistiques`), les valeurs tenues à jour
+    /// sont retournées directement ; sinon, elles sont calculées en parcourant toute la base.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne `(moyennes, écarts types)`, ou `None` si la base est vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Sans suivi actif, panique si les vecteurs stockés n'ont pas tous la même dimension.
+    fn feature_stats(&self) -> Option<(Vec<f32>, Vec<f32>)> {
+        if let Some(statistiques) = &self.statistiques {
+            return statistiques.moyenne_et_ecart_type();
+        }
+
+        let mut statistiques = StatistiquesCourantes::default();
+        for (_, embedding) in self.inner.iter() {
+            assert!(
+                statistiques.nombre == 0 || statistiques.moyenne.len() == embedding.len(),
+                "les statistiques exigent des vecteurs de même dimension"
+            );
+            statistiques.ajouter(embedding);
+        }
+        statistiques.moyenne_et_ecart_type()
+    }
+
+    /// Trouve les dimensions où un vecteur stocké s'écarte le plus de la moyenne de la base.
+    /// 
+    /// Pour chaque dimension `i`, le score z `(xᵢ - moyenneᵢ) / écart_typeᵢ` mesure l'écart du vecteur
+    /// en nombre d'écarts types (voir `feature_stats`) ; une dimension constante dans toute la base a
+    /// un score nul. Les dimensions de plus fort score en valeur absolue expliquent ce qui distingue
+    /// ce vecteur du reste de la base, et donc en partie pourquoi il a été retrouvé.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `uuid` - L'UUID du vecteur à expliquer.
+    /// * `top` - Le nombre de dimensions à retourner.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne au plus `top` paires `(indice de dimension, score z)` triées par valeur absolue
+    /// décroissante du score, puis par indice, ou `None` si l'UUID est absent.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (dimension, z) in db.importance_dimensions(&uuid, 3).expect("UUID présent") {
+    ///     println!("dimension {} : {:+.1} σ", dimension, z);
+    /// }
+    /// ```
+    fn importance_dimensions(&self, uuid: &Uuid, top: usize) -> Option<Vec<(usize, f32)>> {
+        let embedding = self.inner.get(uuid)?;
+        let (moyennes, ecarts_types) = self.feature_stats()?;
+        assert_eq!(
+            embedding.len(),
+            moyennes.len(),
+            "le vecteur doit avoir la dimension des statistiques"
+        );
+
+        let mut scores: Vec<(usize, f32)> = embedding
+            .iter()
+            .zip(moyennes.iter().zip(&ecarts_types))
+            .map(|(x, (moyenne, &ecart_type))| {
+                if ecart_type > 0.0 {
+                    (x - moyenne) / ecart_type
+                } else {
+                    0.0 // Dimension constante : aucun écart
+                }
+            })
+            .enumerate()
+            .collect();
+        scores.sort_by(|a, b| {
+            b.1.abs()
+                .partial_cmp(&a.1.abs())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
+        scores.truncate(top);
+        Some(scores)
+    }
+
+    /// Mesure l'écart entre les structures de covariance de deux bases, indicateur de dérive de
+    /// l'espace d'embarquement entre deux périodes.
+    /// 
+    /// Le résultat est la norme de Frobenius `√(Σᵢⱼ (Aᵢⱼ - Bᵢⱼ)²)` de la différence entre les matrices
+    /// de covariance des deux bases. Il est nul pour deux bases de même covariance, même si leurs
+    /// moyennes diffèrent (une translation de tout l'espace ne change pas sa covariance).
+    /// 
+    /// # Arguments
+    /// 
+    /// * `other` - La base à comparer avec celle-ci.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne la norme de la différence, ou `None` si l'une des bases est vide ou si leurs vecteurs
+    /// n'ont pas la même dimension.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// if janvier.difference_covariance(&fevrier).is_some_and(|derive| derive > 0.1) {
+    ///     println!("l'espace d'embarquement a dérivé");
+    /// }
+    /// ```
+    fn difference_covariance<T: VectorStore>(&self, other: &Db<T>) -> Option<f32> {
+        let (_, a) = self.matrice_covariance()?;
+        let (_, b) = other.matrice_covariance()?;
+        if a.len() != b.len() {
+            return None;
+        }
+
+        let somme: f64 = a
+            .iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum();
+        Some(somme.sqrt() as f32)
+    }
+
+    /// Estime des quantiles de la similarité des vecteurs stockés à une requête, en mémoire bornée.
+    /// 
+    /// Les scores de similarité cosinus sont calculés un à un et versés dans une esquisse de
+    /// Greenwald-Khanna (voir `EsquisseGK`) au lieu d'être tous conservés et triés : la mémoire
+    /// utilisée ne dépend que de `epsilon`, pas de la taille de la base. Le quantile `phi` retourné
+    /// est un score réellement observé dont le rang parmi les `n` scores s'écarte d'au plus `epsilon · n`
+    /// du rang exact `⌈phi · n⌉`.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `query` - Le vecteur de requête.
+    /// * `phis` - Les quantiles demandés, dans `[0, 1]` (`0.5` pour la médiane).
+    /// * `epsilon` - L'erreur de rang tolérée, en proportion du nombre de vecteurs, dans `]0, 1[`.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les couples `(phi, score)` dans l'ordre de `phis`, ou un vecteur vide si la base est
+    /// vide.
+    /// 
+    /// # Panique
+    /// 
+    /// Panique si `epsilon` n'est pas dans `]0, 1[` ou si l'un des `phis` n'est pas dans `[0, 1]`.
+    /// 
+    /// # Exemple
+    /// 
+    /// ```
+    /// for (phi, score) in db.quantiles_approx(&query, &[0.5, 0.9, 0.99], 0.001) {
+    ///     println!("quantile {} : {:.3}", phi, score);
+    /// }
+    /// ```
+    fn quantiles_approx(&self, query: &[f32], phis: &[f32], epsilon: f32) -> Vec<(f32, f32)> {
+        assert!(
+            phis.iter().all(|phi| (0.0..=1.0).contains(phi)),
+            "les quantiles demandés doivent être dans [0, 1]"
+        );
+
+        let mut esquisse = EsquisseGK::new(epsilon);
+        for (_, embedding) in self.inner.iter() {
+            esquisse.ajouter(calcul_similarite(query, embedding));
+        }
+
+        phis.iter()
+            .filter_map(|&phi| esquisse.quantile(phi).map(|score| (phi, score)))
+            .collect()
+    }
+}
+
+
+
+/// Résultat d'une recherche exécutée par `Query::run`.
+/// 
+/// # Champs
+/// 
+/// * `uuid` - L'UUID du vecteur trouvé.
+/// * `score` - Le score du vecteur selon la mesure de la requête (plus élevé = plus proche).
+/// * `metadonnees` - Les métadonnées du vecteur, s'il a été inséré avec `insert_avec_metadonnees`.
+#[derive(Debug, Clone, PartialEq)]
+struct SearchResult {
+    uuid: Uuid,
+    score: f32,
+    metadonnees: Option<Metadonnees>,
+}
+
+/// Échappe une chaîne pour l'écrire entre guillemets dans un document JSON.
+/// 
+/// Les guillemets, les barres obliques inverses et les caractères de contrôle sont échappés ; les
+/// autres caractères, non ASCII compris, sont recopiés tels quels (JSON est en UTF-8).
+fn echapper_json(texte: &str) -> String {
+    let mut echappe = String::with_capacity(texte.len());
+    for c in texte.chars() {
+        match c {
+            '"' => echappe.push_str("\\\""),
+            '\\' => echappe.push_str("\\\\"),
+            '\n' => echappe.push_str("\\n"),
+            '\r' => echappe.push_str("\\r"),
+            '\t' => echappe.push_str("\\t"),
+            c if (c as u32) < 0x20 => echappe.push_str(&format!("\\u{:04x}", c as u32)),
+            c => echappe.push(c),
+        }
+    }
+    echappe
+}
+
+/// Sérialise des résultats de recherche au format NDJSON (un objet JSON par ligne).
+/// 
+/// Chaque ligne a la forme `{"uuid":"…","score":0.87}` et se termine par un saut de ligne, ce qui
+/// permet de pousser les résultats un par un sur un flux NDJSON ou Server-Sent Events. Un score non
+/// fini (`NaN`, infini), qui n'a pas de représentation JSON, est écrit `null`. Un résultat muni de
+/// métadonnées reçoit en plus un champ `"metadata":{"clé":"valeur",…}`, les clés étant triées pour
+/// une sortie déterministe et les clés comme les valeurs échappées selon les règles de JSON.
+/// 
+/// # Arguments
+/// 
+/// * `results` - Les résultats à sérialiser, dans l'ordre souhaité.
+/// 
+/// # Retour
+/// 
+/// Retourne une `String` contenant une ligne par résultat (vide s'il n'y a aucun résultat).
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let corps = results_to_ndjson(&db.query(&query).limit(10).run());
+/// ```
+fn results_to_ndjson(results: &[SearchResult]) -> String {
+    let mut ndjson = String::new();
+    for resultat in results {
+        let score = if resultat.score.is_finite() {
+            resultat.score.to_string()
+        } else {
+            "null".to_string()
+        };
+        ndjson.push_str(&format!("{{\"uuid\":\"{}\",\"score\":{}", resultat.uuid, score));
+        if let Some(metadonnees) = &resultat.metadonnees {
+            let mut paires: Vec<(&String, &String)> = metadonnees.iter().collect();
+            paires.sort();
+            let champs: Vec<String> = paires
+                .into_iter()
+                .map(|(cle, valeur)| format!("\"{}\":\"{}\"", echapper_json(cle), echapper_json(valeur)))
+                .collect();
+            ndjson.push_str(&format!(",\"metadata\":{{{}}}", champs.join(",")));
+        }
+        ndjson.push_str("}\n");
+    }
+    ndjson
+}
+
+/// Requête de recherche construite par étapes à partir de `Db::query`.
+/// 
+/// Les options sont accumulées par des méthodes chaînables, puis la recherche est exécutée une seule
+/// fois par `run`. Par défaut, la requête utilise la mesure par défaut de la base, sans seuil, sans
+/// exclusion, sans écrêtage et sans limite sur le nombre de résultats.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// let resultats = db
+///     .query(&query_embedding)
+///     .metric(Metric::Cosine)
+///     .threshold(0.7)
+///     .exclude(deja_vus)
+///     .limit(10)
+///     .run();
+/// ```
+struct Query<'a, S = HashMap<Uuid, Vec<f32>>> {
+    db: &'a Db<S>,
+    vecteur: &'a [f32],
+    metrique: Metric,
+    seuil: Option<f32>,
+    exclus: HashSet<Uuid>,
+    limite: Option<usize>,
+    ecreter_negatifs: bool,
+}
+
+impl<S: VectorStore> Query<'_, S> {
+    /// Choisit la mesure utilisée pour calculer les scores.
+    fn metric(mut self, metrique: Metric) -> Self {
+        self.metrique = metrique;
+        self
+    }
+
+    /// Ne conserve que les résultats dont le score est supérieur ou égal à `seuil`.
+    fn threshold(mut self, seuil: f32) -> Self {
+        self.seuil = Some(seuil);
+        self
+    }
+
+    /// Exclut les UUIDs donnés des résultats.
+    fn exclude(mut self, exclus: HashSet<Uuid>) -> Self {
+        self.exclus = exclus;
+        self
+    }
+
+    /// Limite le nombre de résultats retournés.
+    fn limit(mut self, n: usize) -> Self {
+        self.limite = Some(n);
+        self
+    }
+
+    /// Ramène à `0.0` les scores négatifs lorsque la mesure est `Metric::DotProduct` (à la manière
+    /// d'une ReLU), pour n'exposer que les alignements positifs.
+    /// 
+    /// Sans effet avec les autres mesures. L'écrêtage a lieu avant le seuil, qui s'applique donc
+    /// aux scores écrêtés.
+    fn clamp_negative(mut self) -> Self {
+        self.ecreter_negatifs = true;
+        self
+    }
+
+    /// Calcule le score d'un vecteur stocké, écrêté si l'option est active.
+    fn score(&self, embedding: &[f32]) -> f32 {
+        let score = self.metrique.score(self.vecteur, embedding);
+        if self.ecreter_negatifs && self.metrique == Metric::DotProduct {
+            score.max(0.0)
+        } else {
+            score
+        }
+    }
+
+    /// Exécute la recherche.
+    /// 
+    /// # Retour
+    /// 
+    /// Retourne les résultats triés par score décroissant, les égalités étant départagées par UUID,
+    /// chacun accompagné de ses métadonnées s'il en a.
+    fn run(self) -> Vec<SearchResult> {
+        let mut scores: Vec<(Uuid, f32)> = self
+            .db
+            .inner
+            .iter()
+            .filter(|(uuid, _)| !self.exclus.contains(uuid))
+            .map(|(uuid, embedding)| (*uuid, self.score(embedding)))
+            .filter(|(_, score)| self.seuil.is_none_or(|seuil| *score >= seuil))
+            .collect();
+
+        trier_par_score(&mut scores);
+        scores.truncate(self.limite.unwrap_or(usize::MAX));
+        self.db.compter_consultations(scores.iter().map(|(uuid, _)| uuid));
+
+        scores
+            .into_iter()
+            .map(|(uuid, score)| SearchResult {
+                uuid,
+                score,
+                metadonnees: self.db.metadonnees.get(&uuid).cloned(),
+            })
+            .collect()
+    }
+}
+
+/// Fonction principale qui simule l'insertion de vecteurs et la recherche de similarités.
+/// 
+/// Dans cette fonction, plusieurs vecteurs sont insérés dans la base de données,
+/// un vecteur de requête est généré et les `n` vecteurs les plus similaires à ce vecteur sont
+/// recherchés et affichés.
+/// 
+/// # Exemple
+/// 
+/// ```
+/// main();
+/// ```
+fn main() {
+    let mut db = Db::new();
+    let embedding_dimension = 768;
+    let phrase = "Ceci est un exemple de phrase";
+
+    // Insérer un vecteur d'embarquement pour chaque mot dans la base de données
+    for _mot in phrase.split_whitespace() {
+        let uuid = Uuid::new_v4();
+        let embedding = generer_embedding(embedding_dimension);
+        db.insert(uuid, embedding);  // On insère chaque mot avec son vecteur d'embarquement
+    }
+
+    // Générer un vecteur de requête
+    let query_embedding = generer_embedding(embedding_dimension);
+
+    // Trouver les 3 vecteurs les plus similaires
+    let plus_similaire = db.trouver_les_plus_similaires(&query_embedding, 3);
+
+    println!("Les 3 vecteurs les plus similaires :");
+    for (uuid, similarity) in plus_similaire {
+        println!("UUID: {}, Similarité: {:.4}", uuid, similarity);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    /// UUID déterministe, pour des départages par UUID prévisibles.
+    fn id(n: u128) -> Uuid {
+        Uuid::from_u128(n)
+    }
+
+    /// Base contenant les vecteurs donnés, sous les UUIDs `1`, `2`, … dans cet ordre d'insertion.
+    fn base(vecteurs: &[Vec<f32>]) -> Db {
+        let mut db = Db::new();
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            db.insert(id(i as u128 + 1), vecteur.clone());
+        }
+        db
+    }
+
+    /// Vecteurs pseudo-aléatoires reproductibles, de composantes dans `[-1, 1[`.
+    fn aleatoires(nombre: usize, dimension: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..nombre)
+            .map(|_| (0..dimension).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    /// Deux groupes bien séparés de `taille` points du plan, autour de `(10, 10)` et de `(-10, -10)`.
+    fn deux_groupes(taille: usize, seed: u64) -> (Db, HashMap<Uuid, usize>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut db = Db::new();
+        let mut groupes = HashMap::new();
+        for i in 0..2 * taille {
+            let (centre, groupe) = if i < taille { (10.0, 0) } else { (-10.0, 1) };
+            let point = vec![centre + rng.gen_range(-1.0..1.0), centre + rng.gen_range(-1.0..1.0)];
+            db.insert(id(i as u128 + 1), point);
+            groupes.insert(id(i as u128 + 1), groupe);
+        }
+        (db, groupes)
+    }
+
+    /// Classement exhaustif de référence : tous les scores, triés par `trier_par_score`.
+    fn classement_complet(db: &Db, query: &[f32]) -> Vec<(Uuid, f32)> {
+        let mut scores: Vec<(Uuid, f32)> = db
+            .inner
+            .iter()
+            .map(|(uuid, embedding)| (*uuid, calcul_similarite(query, embedding)))
+            .collect();
+        trier_par_score(&mut scores);
+        scores
+    }
+
+    fn uuids(resultats: &[(Uuid, f32)]) -> Vec<Uuid> {
+        resultats.iter().map(|(uuid, _)| *uuid).collect()
+    }
+
+    #[test]
+    fn contributions_exemple_et_egalites() {
+        assert_eq!(contributions(&[1.0, 2.0, 3.0], &[4.0, -5.0, 0.0], 2), vec![(1, -10.0), (0, 4.0)]);
+        // À magnitude égale, le plus petit indice d'abord ; `top` au-delà de la dimension ne fait rien
+        assert_eq!(contributions(&[1.0, -1.0, 2.0], &[2.0, 2.0, 0.5], 5), vec![(0, 2.0), (1, -2.0), (2, 1.0)]);
+        assert!(contributions(&[1.0], &[1.0], 0).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "les vecteurs doivent avoir la même dimension")]
+    fn contributions_panique_si_les_dimensions_different() {
+        contributions(&[1.0, 2.0], &[1.0], 1);
+    }
+
+    #[test]
+    fn trier_par_score_departage_par_uuid() {
+        let mut resultats = vec![(id(3), 0.5), (id(1), 0.9), (id(2), 0.5)];
+        trier_par_score(&mut resultats);
+        assert_eq!(resultats, vec![(id(1), 0.9), (id(2), 0.5), (id(3), 0.5)]);
+    }
+
+    #[test]
+    fn trouver_les_plus_similaires_trie_et_tronque() {
+        let db = base(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0], vec![1.0, 0.0]]);
+        let resultats = db.trouver_les_plus_similaires(&[1.0, 0.0], 3);
+        // id(1) et id(4) sont identiques : départage par UUID
+        assert_eq!(uuids(&resultats), vec![id(1), id(4), id(3)]);
+        assert_eq!(resultats, classement_complet(&db, &[1.0, 0.0])[..3].to_vec());
+    }
+
+    #[test]
+    fn departage_par_ordre_d_insertion_favorise_la_derniere_entree() {
+        let mut db = Db::new();
+        db.insert(id(1), vec![1.0, 0.0]);
+        db.insert(id(2), vec![2.0, 0.0]);
+        db.insert(id(3), vec![0.0, 1.0]);
+        let query = [1.0, 0.0];
+        assert_eq!(uuids(&db.trouver_les_plus_similaires_departage(&query, 2, TieBreak::Uuid)), vec![id(1), id(2)]);
+        assert_eq!(
+            uuids(&db.trouver_les_plus_similaires_departage(&query, 2, TieBreak::InsertionOrder)),
+            vec![id(2), id(1)]
+        );
+
+        // Réinsérer id(1) en fait l'entrée la plus récente
+        db.insert(id(1), vec![1.0, 0.0]);
+        assert_eq!(
+            uuids(&db.trouver_les_plus_similaires_departage(&query, 2, TieBreak::InsertionOrder)),
+            vec![id(1), id(2)]
+        );
+
+        // L'ordre survit à une sauvegarde suivie d'un rechargement
+        let mut octets = Vec::new();
+        db.ecrire(&mut octets).expect("écriture en mémoire");
+        let recharge = Db::lire(&mut octets.as_slice()).expect("relecture");
+        assert_eq!(
+            uuids(&recharge.trouver_les_plus_similaires_departage(&query, 2, TieBreak::InsertionOrder)),
+            vec![id(1), id(2)]
+        );
+    }
+
+    #[test]
+    fn calcul_similarite_cas_simples() {
+        assert!((calcul_similarite(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(calcul_similarite(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+        assert!((calcul_similarite(&[1.0, 2.0], &[-1.0, -2.0]) + 1.0).abs() < 1e-6);
+        assert_eq!(calcul_similarite(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn cosine_iter_identique_a_calcul_similarite() {
+        for (query, stored) in aleatoires(20, 64, 1).iter().zip(aleatoires(20, 64, 2).iter()) {
+            assert_eq!(cosine_iter(query.iter().copied(), stored), calcul_similarite(query, stored));
+        }
+        assert_eq!(cosine_iter(std::iter::repeat_n(0.0, 3), &[1.0, 2.0, 3.0]), 0.0);
+    }
+
+    #[test]
+    fn extremes_en_un_seul_parcours() {
+        let db = base(&[vec![1.0, 0.0], vec![1.0, 0.0], vec![-1.0, 0.0], vec![-2.0, 0.0], vec![0.0, 1.0]]);
+        let (proche, eloigne) = db.extremes(&[1.0, 0.0]).expect("base non vide");
+        assert_eq!(proche.0, id(1));
+        assert_eq!(eloigne.0, id(3));
+        assert!((proche.1 - 1.0).abs() < 1e-6 && (eloigne.1 + 1.0).abs() < 1e-6);
+        assert!(Db::new().extremes(&[1.0]).is_none());
+    }
+
+    #[test]
+    fn metric_score_et_distance_sont_coherents() {
+        let (a, b) = ([3.0, 0.0], [0.0, 4.0]);
+        assert_eq!(Metric::Euclidean.distance(&a, &b), 5.0);
+        assert_eq!(Metric::Euclidean.score(&a, &b), -5.0);
+        assert_eq!(Metric::DotProduct.score(&a, &[2.0, 1.0]), 6.0);
+        assert_eq!(Metric::DotProduct.distance(&a, &[2.0, 1.0]), -6.0);
+        assert_eq!(Metric::Cosine.distance(&a, &b), 1.0);
+    }
+
+    #[test]
+    fn query_compose_mesure_seuil_exclusion_et_limite() {
+        let db = base(&[vec![1.0, 0.0], vec![0.9, 0.1], vec![0.0, 1.0], vec![-1.0, 0.0]]);
+        let query = [1.0, 0.0];
+
+        let resultats = db.query(&query).exclude(HashSet::from([id(1)])).threshold(0.0).limit(2).run();
+        let trouves: Vec<Uuid> = resultats.iter().map(|r| r.uuid).collect();
+        assert_eq!(trouves, vec![id(2), id(3)]);
+
+        let tous = db.query(&query).run();
+        assert_eq!(tous.len(), 4);
+        assert!(tous.windows(2).all(|paire| paire[0].score >= paire[1].score));
+
+        let euclidienne = db.query(&query).metric(Metric::Euclidean).limit(1).run();
+        assert_eq!(euclidienne[0].uuid, id(1));
+        assert_eq!(euclidienne[0].score, 0.0);
+    }
+
+    #[test]
+    fn dimension_intrinseque_d_un_plan_plonge() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut db = Db::new();
+        for i in 0..400 {
+            let (u, v): (f32, f32) = (rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
+            let mut point = vec![0.0f32; 10];
+            point[0] = u;
+            point[3] = v;
+            point[7] = u + v;
+            db.insert(id(i), point);
+        }
+        let dimension = db.dimension_intrinseque(400, 42).expect("assez de points");
+        assert!(dimension > 1.5 && dimension < 2.5, "dimension estimée : {}", dimension);
+        assert_eq!(db.dimension_intrinseque(400, 42), Some(dimension));
+        assert!(base(&[vec![1.0], vec![2.0]]).dimension_intrinseque(10, 0).is_none());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn export_parquet_ecrit_un_fichier_parquet() {
+        let db = base(&aleatoires(10, 3, 50));
+        let chemin = std::env::temp_dir().join(format!("tprust-export-{}.parquet", std::process::id()));
+        db.to_parquet(&chemin).unwrap();
+        let octets = std::fs::read(&chemin).unwrap();
+        std::fs::remove_file(&chemin).unwrap();
+        assert!(octets.starts_with(b"PAR1") && octets.ends_with(b"PAR1"));
+    }
+
+    #[test]
+    fn diff_ajouts_suppressions_et_modifications() {
+        let avant = base(&[vec![1.0], vec![2.0], vec![3.0]]);
+        let mut apres = base(&[vec![1.0], vec![2.0], vec![3.5]]);
+        apres.remove(&id(1));
+        apres.insert(id(4), vec![4.0]);
+
+        let changements = avant.diff(&apres);
+        assert_eq!(changements.ajoutes, HashSet::from([id(4)]));
+        assert_eq!(changements.supprimes, HashSet::from([id(1)]));
+        assert_eq!(changements.modifies, HashSet::from([id(3)]));
+        assert_eq!(avant.diff(&avant), DbDiff::default());
+    }
+
+    #[test]
+    fn cosine_with_cache_egale_le_cosinus() {
+        let vecteurs = aleatoires(10, 8, 9);
+        let db = base(&vecteurs);
+        let cache = db.norm_cache();
+        let query = &aleatoires(1, 8, 10)[0];
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            let avec_cache = db.cosine_with_cache(norme(query), query, &id(i as u128 + 1), &cache);
+            assert!((avec_cache - calcul_similarite(query, vecteur)).abs() < 1e-6);
+        }
+        assert_eq!(db.cosine_with_cache(norme(query), query, &id(999), &cache), 0.0);
+    }
+
+    #[test]
+    fn merge_top_n_egale_le_tri_de_l_union_egalites_comprises() {
+        let mut rng = StdRng::seed_from_u64(6);
+        // Des scores en petit nombre de valeurs distinctes pour multiplier les égalités entre shards
+        let shards: Vec<Vec<(Uuid, f32)>> = (0..4)
+            .map(|_| {
+                let mut shard: Vec<(Uuid, f32)> = (0..25)
+                    .map(|_| (id(rng.gen_range(0..1_000_000)), rng.gen_range(0..5) as f32 / 4.0))
+                    .collect();
+                trier_par_score(&mut shard);
+                shard
+            })
+            .collect();
+        let mut union: Vec<(Uuid, f32)> = shards.iter().flatten().copied().collect();
+        trier_par_score(&mut union);
+
+        for n in [0, 1, 7, 50, 100, 150] {
+            let attendu: Vec<(Uuid, f32)> = union.iter().take(n).copied().collect();
+            assert_eq!(merge_top_n(&shards, n), attendu);
+        }
+        assert!(merge_top_n(&[Vec::new(), Vec::new()], 3).is_empty());
+    }
+
+    #[test]
+    fn jensen_shannon_nulle_pour_identiques_et_un_pour_disjointes() {
+        assert_eq!(jensen_shannon(&[0.5, 0.5], &[0.5, 0.5]), 0.0);
+        assert!((jensen_shannon(&[1.0, 0.0], &[0.0, 1.0]) - 1.0).abs() < 1e-6);
+        let (p, q) = ([0.2, 0.3, 0.5], [0.6, 0.1, 0.3]);
+        let divergence = jensen_shannon(&p, &q);
+        assert!(divergence > 0.0 && divergence < 1.0);
+        assert!((divergence - jensen_shannon(&q, &p)).abs() < 1e-7);
+    }
+
+    #[test]
+    #[should_panic(expected = "une distribution doit sommer à 1")]
+    fn jensen_shannon_panique_hors_distribution() {
+        jensen_shannon(&[0.5, 0.6], &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn trouver_par_jensen_shannon_classe_par_divergence_croissante() {
+        let db = base(&[vec![0.0, 1.0], vec![0.5, 0.5], vec![0.9, 0.1]]);
+        let resultats = db.trouver_par_jensen_shannon(&[1.0, 0.0], 3);
+        assert_eq!(uuids(&resultats), vec![id(3), id(2), id(1)]);
+        assert!(resultats.windows(2).all(|paire| paire[0].1 <= paire[1].1));
+    }
+
+    #[test]
+    fn mmr_diversifie_apres_le_plus_pertinent() {
+        // id(2) est un quasi-doublon de id(1) ; id(3) est moins pertinent mais différent
+        let db = base(&[vec![1.0, 0.0], vec![0.99, 0.1], vec![0.6, 0.8]]);
+        let query = [1.0, 0.0];
+        assert_eq!(uuids(&db.mmr(&query, 2, 1.0)), vec![id(1), id(2)]);
+        assert_eq!(uuids(&db.mmr(&query, 2, 0.1)), vec![id(1), id(3)]);
+        for lambda in [0.0, 0.3, 0.7] {
+            assert_eq!(db.mmr(&query, 1, lambda)[0].0, id(1));
+        }
+        // Les scores retournés sont les similarités à la requête
+        let resultats = db.mmr(&query, 3, 0.5);
+        assert_eq!(resultats.len(), 3);
+        assert!((resultats[0].1 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn compter_dans_rayon_suit_le_filtre() {
+        let vecteurs = aleatoires(50, 6, 11);
+        let db = base(&vecteurs);
+        let query = &aleatoires(1, 6, 12)[0];
+        let seuil = 0.2;
+        let attendus = vecteurs.iter().filter(|v| calcul_similarite(query, v) >= seuil).count();
+        assert_eq!(db.compter_dans_rayon(query, seuil), attendus);
+
+        // Le seuil est inclusif
+        let exact = base(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(exact.compter_dans_rayon(&[1.0, 0.0], 0.0), 2);
+    }
+
+    #[test]
+    fn cosine_masquee_ignore_les_dimensions_masquees() {
+        let similarite = cosine_masquee(&[1.0, 2.0, 9.0, 9.0], &[1.0, 2.0, -9.0, 0.0], &[true, true, false, false]);
+        assert!((similarite - 1.0).abs() < 1e-6);
+        let complet = cosine_masquee(&[1.0, 2.0], &[3.0, -1.0], &[true, true]);
+        assert!((complet - calcul_similarite(&[1.0, 2.0], &[3.0, -1.0])).abs() < 1e-6);
+        assert_eq!(cosine_masquee(&[1.0, 2.0], &[3.0, 4.0], &[false, false]), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "le masque doit avoir la dimension des vecteurs")]
+    fn cosine_masquee_panique_si_le_masque_est_trop_court() {
+        cosine_masquee(&[1.0, 2.0], &[3.0, 4.0], &[true]);
+    }
+
+    #[test]
+    fn knn_graph_exclut_chaque_vecteur_de_ses_voisins() {
+        let db = base(&aleatoires(15, 4, 16));
+        let graphe = db.knn_graph(3);
+        assert_eq!(graphe.len(), 15);
+        for (uuid, voisins) in &graphe {
+            assert_eq!(voisins.len(), 3);
+            assert!(voisins.iter().all(|(voisin, _)| voisin != uuid));
+            assert!(voisins.windows(2).all(|paire| paire[0].1 >= paire[1].1));
+        }
+    }
+
+    #[test]
+    fn decroissance_temporelle_favorise_les_entrees_recentes() {
+        let mut db = Db::new();
+        let maintenant = SystemTime::now();
+        db.insert_avec_horodatage(id(1), vec![1.0, 0.0], maintenant - Duration::from_secs(10 * 86_400));
+        db.insert(id(2), vec![1.0, 0.0]);
+        let resultats = db.trouver_avec_decroissance(&[1.0, 0.0], 2, Duration::from_secs(86_400));
+        assert_eq!(uuids(&resultats), vec![id(2), id(1)]);
+        assert!((resultats[0].1 - 1.0).abs() < 1e-3);
+        assert!((resultats[1].1 - 0.5f32.powi(10)).abs() < 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "la demi-vie doit être strictement positive")]
+    fn decroissance_temporelle_panique_sans_demi_vie() {
+        base(&[vec![1.0]]).trouver_avec_decroissance(&[1.0], 1, Duration::ZERO);
+    }
+
+    #[test]
+    fn silhouette_proche_de_un_sur_des_groupes_separes() {
+        let (db, groupes) = deux_groupes(10, 17);
+        assert!(db.silhouette(&groupes, Metric::Euclidean) > 0.9);
+        let un_seul: HashMap<Uuid, usize> = groupes.keys().map(|uuid| (*uuid, 0)).collect();
+        assert_eq!(db.silhouette(&un_seul, Metric::Euclidean), 0.0);
+    }
+
+    #[test]
+    fn reservoir_reproductible_et_borne() {
+        let flux = aleatoires(100, 3, 19);
+        let remplir = |seed: u64| {
+            let mut db = Db::new().avec_reservoir(10, seed);
+            for (i, vecteur) in flux.iter().enumerate() {
+                db.insert(id(i as u128), vecteur.clone());
+            }
+            db
+        };
+        let (a, b) = (remplir(42), remplir(42));
+        assert_eq!(a.reservoir().len(), 10);
+        assert_eq!(a.reservoir(), b.reservoir());
+        assert!(a.reservoir().iter().all(|(uuid, vecteur)| &flux[uuid.as_u128() as usize] == vecteur));
+        assert!(Db::new().reservoir().is_empty());
+    }
+
+    #[test]
+    fn similarite_entre_utilise_la_mesure_de_la_base() {
+        let db = base(&[vec![1.0, 2.0], vec![3.0, 4.0]]);
+        let cosinus = db.similarite_entre(&id(1), &id(2)).expect("UUIDs présents");
+        assert!((cosinus - calcul_similarite(&[1.0, 2.0], &[3.0, 4.0])).abs() < 1e-6);
+        assert!(db.similarite_entre(&id(1), &id(3)).is_none());
+        let produit = base(&[vec![1.0, 2.0], vec![3.0, 4.0]]).avec_metrique(Metric::DotProduct);
+        assert_eq!(produit.similarite_entre(&id(1), &id(2)), Some(11.0));
+    }
+
+    #[test]
+    fn ndjson_echappe_les_metadonnees_et_les_scores_non_finis() {
+        let mut metadonnees = Metadonnees::new();
+        metadonnees.insert("titre".to_string(), "ligne\n\"citée\"\u{1}".to_string());
+        metadonnees.insert("auteur".to_string(), "a\\b".to_string());
+        let resultats = vec![
+            SearchResult { uuid: id(1), score: 0.5, metadonnees: Some(metadonnees) },
+            SearchResult { uuid: id(2), score: f32::NAN, metadonnees: None },
+        ];
+        let attendu = format!(
+            "{{\"uuid\":\"{}\",\"score\":0.5,\"metadata\":{{\"auteur\":\"a\\\\b\",\"titre\":\"ligne\\n\\\"citée\\\"\\u0001\"}}}}\n\
+             {{\"uuid\":\"{}\",\"score\":null}}\n",
+            id(1),
+            id(2)
+        );
+        assert_eq!(results_to_ndjson(&resultats), attendu);
+        assert_eq!(results_to_ndjson(&[]), "");
+    }
+
+    #[test]
+    fn query_run_joint_les_metadonnees() {
+        let mut db = Db::new();
+        let mut metadonnees = Metadonnees::new();
+        metadonnees.insert("source".to_string(), "wiki".to_string());
+        db.insert_avec_metadonnees(id(1), vec![1.0, 0.0], metadonnees.clone());
+        db.insert(id(2), vec![0.0, 1.0]);
+        let resultats = db.query(&[1.0, 0.0]).run();
+        assert_eq!(resultats[0].metadonnees, Some(metadonnees));
+        assert_eq!(resultats[1].metadonnees, None);
+    }
+
+    #[test]
+    fn lloyd_n_augmente_pas_l_inertie_initiale() {
+        let points = aleatoires(40, 3, 7);
+        let vues: Vec<&[f32]> = points.iter().map(Vec::as_slice).collect();
+        let initiaux = vec![points[0].clone(), points[1].clone(), points[2].clone()];
+        let inertie_initiale: f32 = vues.iter().map(|p| centre_le_plus_proche(&initiaux, p).1).sum();
+        let (groupes, centroides, inertie) = lloyd(&vues, initiaux, 50);
+        assert_eq!(groupes.len(), 40);
+        assert_eq!(centroides.len(), 3);
+        assert!(inertie <= inertie_initiale + 1e-4);
+    }
+
+    #[test]
+    fn kmeans_separe_deux_groupes() {
+        let (db, groupes) = deux_groupes(15, 20);
+        let partition = db.kmeans(2, 100, 42);
+        assert_eq!(partition.centroides.len(), 2);
+        for (uuid, &groupe) in &groupes {
+            for (autre, &groupe_autre) in &groupes {
+                let meme = partition.affectations[uuid] == partition.affectations[autre];
+                assert_eq!(meme, groupe == groupe_autre);
+            }
+        }
+        assert!(partition.inertie < 30.0 * 2.0);
+        assert!(Db::new().kmeans(3, 10, 0).affectations.is_empty());
+    }
+
+    #[test]
+    fn elbow_donne_une_inertie_decroissante() {
+        let db = base(&aleatoires(80, 4, 23));
+        let courbe = db.elbow(1..12, 42);
+        assert_eq!(courbe.iter().map(|(k, _)| *k).collect::<Vec<_>>(), (1..12).collect::<Vec<_>>());
+        for paire in courbe.windows(2) {
+            assert!(paire[1].1 <= paire[0].1 * (1.0 + 1e-5), "{:?}", courbe);
+        }
+        assert!(courbe.last().unwrap().1 < courbe[0].1);
+    }
+
+    #[test]
+    fn appliquer_transformation_identite_et_permutation() {
+        let vecteurs = vec![vec![1.0, 2.0], vec![-3.0, 0.5]];
+        let mut db = base(&vecteurs);
+        db.appliquer_transformation(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(db.inner[&id(1)], vec![1.0, 2.0]);
+        db.appliquer_transformation(&[vec![0.0, 1.0], vec![1.0, 0.0]]);
+        assert_eq!(db.inner[&id(1)], vec![2.0, 1.0]);
+        assert_eq!(db.inner[&id(2)], vec![0.5, -3.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "la matrice de transformation doit être carrée")]
+    fn appliquer_transformation_panique_sur_une_matrice_non_carree() {
+        base(&[vec![1.0, 2.0]]).appliquer_transformation(&[vec![1.0, 0.0]]);
+    }
+
+    #[test]
+    fn trouver_avec_elagage_identique_a_la_force_brute_avec_moins_de_calculs() {
+        // L'essentiel de la norme est porté par le premier quart des dimensions, comme après une ACP
+        let mut rng = StdRng::seed_from_u64(24);
+        let mut vecteurs = Vec::new();
+        for _ in 0..400 {
+            let vecteur: Vec<f32> = (0..32)
+                .map(|d| if d < 8 { rng.gen_range(-1.0..1.0) } else { rng.gen_range(-0.05..0.05) })
+                .collect();
+            vecteurs.push(vecteur);
+        }
+        let db = base(&vecteurs);
+        let cache = db.norm_cache();
+        for query in &vecteurs[..5] {
+            let (resultats, calculs_complets) = db.trouver_avec_elagage(query, 5, &cache);
+            assert_eq!(resultats, classement_complet(&db, query)[..5].to_vec());
+            assert!(calculs_complets < 400 / 2, "{} calculs complets", calculs_complets);
+        }
+        assert_eq!(db.trouver_avec_elagage(&vecteurs[0], 0, &cache), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn trouver_groupe_par_ne_retourne_qu_une_entree_par_groupe() {
+        let mut db = Db::new();
+        let vecteurs = aleatoires(30, 4, 25);
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            let mut metadonnees = Metadonnees::new();
+            metadonnees.insert("source".to_string(), format!("doc{}", i % 4));
+            db.insert_avec_metadonnees(id(i as u128), vecteur.clone(), metadonnees);
+        }
+        db.insert(id(100), vec![1.0, 1.0, 1.0, 1.0]); // Sans métadonnées : ignorée
+
+        let query = [1.0, 1.0, 1.0, 1.0];
+        let resultats = db.trouver_groupe_par(&query, 10, |m| m.get("source").cloned());
+        assert_eq!(resultats.len(), 4);
+        let sources: HashSet<u128> = resultats.iter().map(|(uuid, _)| uuid.as_u128() % 4).collect();
+        assert_eq!(sources.len(), 4);
+        assert!(resultats.iter().all(|(uuid, _)| *uuid != id(100)));
+        // Chaque résultat est le meilleur de son groupe
+        for (uuid, score) in &resultats {
+            let groupe = uuid.as_u128() % 4;
+            let meilleur = (0..30u128)
+                .filter(|i| i % 4 == groupe)
+                .map(|i| calcul_similarite(&query, &vecteurs[i as usize]))
+                .fold(f32::NEG_INFINITY, f32::max);
+            assert_eq!(*score, meilleur);
+        }
+    }
+
+    #[test]
+    fn nb_groupes_approx_compte_les_quasi_doublons_une_fois() {
+        let mut rng = StdRng::seed_from_u64(26);
+        let directions = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let mut db = Db::new();
+        for i in 0..30u128 {
+            let direction = directions[(i % 3) as usize];
+            db.insert(id(i), direction.iter().map(|x| x + rng.gen_range(-0.01..0.01)).collect());
+        }
+        assert_eq!(db.nb_groupes_approx(0.99), 3);
+        assert_eq!(db.nb_groupes_approx(1.1), 30);
+    }
+
+    #[test]
+    fn lire_rejette_une_signature_invalide() {
+        let erreur = Db::lire(&mut &b"XXXX\x01"[..]).err().expect("signature refusée");
+        assert_eq!(erreur.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn sauvegarde_asynchrone_relue_a_l_identique() {
+        let db = std::sync::Arc::new(base(&aleatoires(20, 3, 49)));
+        let chemin = std::env::temp_dir().join(format!("tprust-async-{}.db", std::process::id()));
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let relue = runtime.block_on(async {
+            std::sync::Arc::clone(&db).save_async(&chemin).await.unwrap();
+            <Db>::load_async(&chemin).await.unwrap()
+        });
+        std::fs::remove_file(&chemin).unwrap();
+        assert_eq!(relue.empreinte(), db.empreinte());
+    }
+
+    #[test]
+    fn couverture_clusters_compte_les_resultats_par_groupe() {
+        let (db, groupes) = deux_groupes(10, 46);
+        let couverture = db.couverture_clusters(&[10.0, 10.0], 5, &groupes);
+        assert_eq!(couverture, HashMap::from([(0, 5)]));
+        let couverture = db.couverture_clusters(&[10.0, 10.0], 20, &groupes);
+        assert_eq!(couverture, HashMap::from([(0, 10), (1, 10)]));
+        assert!(db.couverture_clusters(&[10.0, 10.0], 5, &HashMap::new()).is_empty());
+    }
+
+    /// Stockage ordonné par UUID, pour vérifier que `Db` ne dépend que du trait `VectorStore`.
+    #[derive(Default)]
+    struct StockageOrdonne(BTreeMap<Uuid, Vec<f32>>);
+
+    impl VectorStore for StockageOrdonne {
+        fn get(&self, uuid: &Uuid) -> Option<&[f32]> {
+            self.0.get(uuid).map(Vec::as_slice)
+        }
+
+        fn insert(&mut self, uuid: Uuid, embedding: Vec<f32>) -> Option<Vec<f32>> {
+            self.0.insert(uuid, embedding)
+        }
+
+        fn remove(&mut self, uuid: &Uuid) -> Option<Vec<f32>> {
+            self.0.remove(uuid)
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = (&Uuid, &[f32])> + '_> {
+            Box::new(self.0.iter().map(|(uuid, embedding)| (uuid, embedding.as_slice())))
+        }
+
+        fn len(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[test]
+    fn stockage_personnalise_donne_les_memes_resultats() {
+        let vecteurs = aleatoires(30, 4, 47);
+        let reference = base(&vecteurs);
+        let mut ordonnee = Db::avec_stockage(StockageOrdonne::default());
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            ordonnee.insert(id(i as u128 + 1), vecteur.clone());
+        }
+        let query = [0.1, 0.2, -0.3, 0.4];
+        assert_eq!(ordonnee.trouver_les_plus_similaires(&query, 5), reference.trouver_les_plus_similaires(&query, 5));
+        assert_eq!(ordonnee.empreinte(), reference.empreinte());
+        assert!(ordonnee.remove(&id(1)).is_some() && ordonnee.remove(&id(1)).is_none());
+        assert_eq!(ordonnee.inner.len(), 29);
+    }
+
+    #[test]
+    fn trouver_centre_retire_la_direction_commune() {
+        // Tous les vecteurs partagent une forte composante commune qui écrase le cosinus brut
+        let mut rng = StdRng::seed_from_u64(27);
+        let mut db = Db::new().avec_centroide();
+        for i in 0..40u128 {
+            let mut point = vec![100.0f32, 0.0, 0.0];
+            point[1] = rng.gen_range(-1.0..1.0);
+            point[2] = rng.gen_range(-1.0..1.0);
+            db.insert(id(i), point);
+        }
+        let query = [100.0, 1.0, 0.0];
+        let brut = db.trouver_les_plus_similaires(&query, 40);
+        let centre = db.trouver_centre(&query, 40);
+        assert!(brut.iter().all(|(_, score)| *score > 0.99));
+        assert!(centre.last().unwrap().1 < 0.5);
+        assert!(centre.windows(2).all(|paire| paire[0].1 >= paire[1].1));
+        assert!(Db::new().trouver_centre(&query, 5).is_empty());
+    }
+
+    #[test]
+    fn centroide_suivi_egale_le_centroide_recalcule() {
+        let vecteurs = aleatoires(20, 4, 28);
+        let mut suivi = Db::new().avec_centroide();
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            suivi.insert(id(i as u128), vecteur.clone());
+        }
+        suivi.remove(&id(3));
+        suivi.insert(id(5), vec![1.0, 1.0, 1.0, 1.0]);
+
+        let mut attendu = vec![0.0f32; 4];
+        for (_, embedding) in suivi.inner.iter() {
+            for (a, x) in attendu.iter_mut().zip(embedding.iter()) {
+                *a += x / 19.0;
+            }
+        }
+        let centroide = suivi.centroide().expect("base non vide");
+        assert!(centroide.iter().zip(&attendu).all(|(c, a)| (c - a).abs() < 1e-5));
+    }
+
+    #[test]
+    fn insert_checked_signale_les_collisions_sans_ecraser() {
+        let mut db = Db::new();
+        assert_eq!(db.insert_checked(id(1), vec![1.0]), Ok(()));
+        assert_eq!(db.insert_checked(id(1), vec![2.0]), Err(id(1)));
+        assert_eq!(db.inner[&id(1)], vec![1.0]);
+
+        let lot = vec![(id(2), vec![2.0]), (id(1), vec![9.0]), (id(2), vec![9.0]), (id(3), vec![3.0])];
+        assert_eq!(db.insert_many_checked(lot), Err(vec![id(1), id(2)]));
+        assert_eq!(db.inner.len(), 3);
+        assert_eq!(db.inner[&id(2)], vec![2.0]);
+        assert_eq!(db.insert_many_checked(vec![(id(4), vec![4.0])]), Ok(()));
+    }
+
+    /// Produits scalaires de référence, calculés ligne par ligne sans BLAS.
+    fn produits_scalaires_scalaires(index: &IndexMatriciel, query: &[f32]) -> Vec<f32> {
+        index.vecteurs.chunks_exact(index.dimension).map(|ligne| produit_scalaire(query, ligne)).collect()
+    }
+
+    #[test]
+    fn index_matriciel_concorde_avec_le_calcul_scalaire() {
+        let db = base(&aleatoires(200, 300, 55));
+        let index = db.index_matriciel();
+        for query in aleatoires(5, 300, 56) {
+            let produits = index.produits_scalaires(&query);
+            let attendus = produits_scalaires_scalaires(&index, &query);
+            assert_eq!(produits.len(), attendus.len());
+            for (produit, attendu) in produits.iter().zip(&attendus) {
+                // La BLAS peut réordonner les sommes : l'écart reste de l'ordre de l'arrondi
+                if cfg!(feature = "blas") {
+                    assert!((produit - attendu).abs() <= 1e-4 * (1.0 + attendu.abs()), "{} contre {}", produit, attendu);
+                } else {
+                    assert_eq!(produit.to_bits(), attendu.to_bits());
+                }
+            }
+            let resultats = index.trouver_les_plus_similaires(&query, 10);
+            assert_eq!(uuids(&resultats), uuids(&db.trouver_sans_compter(&query, 10, TieBreak::Uuid)));
+        }
+        assert!(Db::new().index_matriciel().produits_scalaires(&[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "la requête doit avoir la dimension de l'index")]
+    fn index_matriciel_panique_sur_une_requete_de_mauvaise_dimension() {
+        base(&[vec![1.0, 0.0]]).index_matriciel().produits_scalaires(&[1.0]);
+    }
+
+    /// Compare le produit matrice-vecteur de `IndexMatriciel` au calcul scalaire ligne par ligne, sur
+    /// 100 000 vecteurs de dimension 1536 (environ 600 Mo).
+    /// 
+    /// À lancer en mode optimisé, avec et sans BLAS :
+    /// `cargo test --release --features blas -- --ignored --nocapture banc_blas`.
+    #[test]
+    #[ignore]
+    fn banc_blas_produits_scalaires_en_dimension_1536() {
+        use std::time::Instant;
+
+        const NOMBRE: usize = 100_000;
+        const DIMENSION: usize = 1536;
+        let mut rng = StdRng::seed_from_u64(57);
+        let vecteurs: Vec<f32> = (0..NOMBRE * DIMENSION).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        let normes = vecteurs.chunks_exact(DIMENSION).map(norme).collect();
+        let index = IndexMatriciel { uuids: (0..NOMBRE as u128).map(id).collect(), vecteurs, dimension: DIMENSION, normes };
+        let requetes = aleatoires(10, DIMENSION, 58);
+
+        let debut = Instant::now();
+        for query in &requetes {
+            produits_scalaires_scalaires(&index, query);
+        }
+        let duree_scalaire = debut.elapsed();
+
+        let debut = Instant::now();
+        for query in &requetes {
+            index.produits_scalaires(query);
+        }
+        let duree_index = debut.elapsed();
+
+        println!(
+            "{} vecteurs × {} dimensions, {} requêtes : scalaire {:?}, IndexMatriciel ({}) {:?}",
+            NOMBRE,
+            DIMENSION,
+            requetes.len(),
+            duree_scalaire,
+            if cfg!(feature = "blas") { "BLAS" } else { "sans BLAS" },
+            duree_index
+        );
+    }
+
+    #[test]
+    fn histogramme_scores_repartit_chaque_vecteur() {
+        let db = base(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![-1.0, 0.0], vec![1.0, 1.0]]);
+        let histogramme = db.histogramme_scores(&[1.0, 0.0], 4);
+        let comptes: Vec<usize> = histogramme.iter().map(|(_, _, nombre)| *nombre).collect();
+        assert_eq!(comptes, vec![1, 0, 1, 2]);
+        assert_eq!((histogramme[0].0, histogramme[3].1), (-1.0, 1.0));
+        assert!(db.histogramme_scores(&[1.0, 0.0], 0).is_empty());
+        assert!(Db::new().histogramme_scores(&[1.0], 3).is_empty());
+    }
+
+    #[test]
+    fn plus_centraux_et_plus_aberrants_sont_opposes() {
+        let mut vecteurs: Vec<Vec<f32>> = (0..10).map(|i| vec![1.0, 0.1 * i as f32 - 0.5]).collect();
+        vecteurs.push(vec![-1.0, 0.2]);
+        let db = base(&vecteurs);
+        assert_eq!(db.plus_aberrants(1)[0].0, id(11));
+        let centraux = db.plus_centraux(11);
+        assert_eq!(centraux.last().unwrap().0, id(11));
+        let mut inverse = db.plus_aberrants(11);
+        inverse.reverse();
+        assert_eq!(inverse, centraux);
+        assert!(Db::new().plus_centraux(3).is_empty() && Db::new().plus_aberrants(3).is_empty());
+    }
+
+    #[test]
+    fn try_search_distingue_la_base_vide() {
+        assert_eq!(Db::new().try_search(&[1.0], 3), Err(SearchError::EmptyDatabase));
+        let db = base(&[vec![1.0, 0.0]]);
+        assert_eq!(db.try_search(&[1.0, 0.0], 0), Ok(Vec::new()));
+        assert_eq!(db.try_search(&[1.0, 0.0], 1).map(|r| r.len()), Ok(1));
+    }
+
+    #[test]
+    fn clamp_negative_ramene_les_produits_negatifs_a_zero() {
+        let db = base(&[vec![1.0, 0.0], vec![-3.0, 0.0]]).avec_metrique(Metric::DotProduct);
+        let resultats = db.query(&[1.0, 0.0]).clamp_negative().run();
+        assert_eq!(resultats[1].uuid, id(2));
+        assert_eq!(resultats[1].score, 0.0);
+        assert!(resultats[1].score.is_sign_positive());
+
+        // Le seuil s'applique aux scores écrêtés
+        assert_eq!(db.query(&[1.0, 0.0]).clamp_negative().threshold(0.0).run().len(), 2);
+        // Sans effet avec le cosinus
+        let cosinus = db.query(&[1.0, 0.0]).metric(Metric::Cosine).clamp_negative().run();
+        assert!((cosinus[1].score + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn conformer_dimension_complete_ou_tronque() {
+        let mut db = base(&[vec![1.0, 2.0], vec![3.0, 4.0, 5.0, 6.0]]);
+        db.conformer_dimension(4, ConformMode::Pad);
+        assert_eq!(db.inner[&id(1)], vec![1.0, 2.0, 0.0, 0.0]);
+        assert_eq!(db.inner[&id(2)], vec![3.0, 4.0, 5.0, 6.0]);
+        db.conformer_dimension(3, ConformMode::Truncate);
+        assert_eq!(db.inner[&id(1)], vec![1.0, 2.0, 0.0]);
+        assert_eq!(db.inner[&id(2)], vec![3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "impossible de compléter un vecteur de dimension supérieure à 2")]
+    fn conformer_dimension_panique_sans_modifier_la_base() {
+        let mut db = base(&[vec![1.0], vec![1.0, 2.0, 3.0]]);
+        db.conformer_dimension(2, ConformMode::Pad);
+    }
+
+    /// Index qui retourne les pires résultats au lieu des meilleurs, pour simuler un index dégradé.
+    struct IndexInverse<'a>(&'a Db);
+
+    impl RechercheApprochee for IndexInverse<'_> {
+        fn rechercher(&self, query: &[f32], k: usize) -> Vec<(Uuid, f32)> {
+            let mut classement = self.0.trouver_sans_compter(query, self.0.inner.len(), TieBreak::Uuid);
+            classement.reverse();
+            classement.truncate(k);
+            classement
+        }
+    }
+
+    #[test]
+    fn moniteur_rappel_mesure_la_qualite_de_l_index_approche() {
+        let db = base(&aleatoires(50, 4, 33));
+        let requetes = aleatoires(10, 4, 34);
+
+        let mut parfait = MoniteurRappel::new(5, 1.0, 100, 42);
+        let mut degrade = MoniteurRappel::new(5, 1.0, 100, 42);
+        for query in &requetes {
+            assert_eq!(parfait.observer(&db, &db, query), Some(1.0));
+            assert_eq!(degrade.observer(&IndexInverse(&db), &db, query), Some(0.0));
+        }
+        assert_eq!(parfait.recall_courant(), 1.0);
+        assert_eq!(degrade.recall_courant(), 0.0);
+
+        // Seules les `fenetre` dernières mesures comptent
+        let mut glissant = MoniteurRappel::new(5, 1.0, 2, 42);
+        glissant.observer(&IndexInverse(&db), &db, &requetes[0]);
+        glissant.observer(&db, &db, &requetes[1]);
+        glissant.observer(&db, &db, &requetes[2]);
+        assert_eq!(glissant.recall_courant(), 1.0);
+
+        let mut jamais = MoniteurRappel::new(5, 0.0, 10, 42);
+        assert!(requetes.iter().all(|query| jamais.observer(&db, &db, query).is_none()));
+        assert_eq!(jamais.recall_courant(), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "la fraction échantillonnée doit être dans [0, 1]")]
+    fn moniteur_rappel_panique_sur_une_fraction_invalide() {
+        MoniteurRappel::new(5, 1.5, 10, 0);
+    }
+
+    #[test]
+    fn recherche_par_blocs_identique_quelle_que_soit_la_taille() {
+        let mut vecteurs = aleatoires(60, 5, 29);
+        // Des doublons exacts pour exercer le départage des égalités à la frontière des blocs
+        for i in 0..10 {
+            vecteurs.push(vecteurs[i].clone());
+        }
+        let db = base(&vecteurs);
+        for query in &vecteurs[..3] {
+            for n in [0, 1, 5, 70, 100] {
+                let attendu = db.trouver_sans_compter(query, n, TieBreak::Uuid);
+                for chunk in [1, 3, 7, 64, 1000] {
+                    assert_eq!(db.trouver_les_plus_similaires_chunked(query, n, chunk), attendu);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "la taille de bloc doit être strictement positive")]
+    fn recherche_par_blocs_panique_sur_un_bloc_vide() {
+        base(&[vec![1.0]]).trouver_les_plus_similaires_chunked(&[1.0], 1, 0);
+    }
+
+    #[test]
+    fn insertion_paresseuse_appelle_chaque_fabrique_une_fois() {
+        let mut db = Db::new();
+        db.insert_lazy(id(1), || vec![1.0, 2.0]);
+        assert_eq!(db.inner[&id(1)], vec![1.0, 2.0]);
+
+        let fabriques = (0..5u128).map(|i| {
+            let fabrique: Box<dyn FnOnce() -> Vec<f32>> = Box::new(move || vec![i as f32]);
+            (id(i), fabrique)
+        });
+        let db = Db::new().avec_fabriques(fabriques);
+        assert_eq!(db.inner.len(), 5);
+        assert_eq!(db.inner[&id(4)], vec![4.0]);
+    }
+
+    #[test]
+    fn approx_egal_tolere_un_ecart_borne() {
+        assert!(approx_egal(&[1.0, 2.0], &[1.0005, 1.9995], 1e-3));
+        assert!(!approx_egal(&[1.0, 2.0], &[1.0, 2.01], 1e-3));
+        assert!(!approx_egal(&[1.0, 2.0], &[1.0], 1e-3));
+        assert!(approx_egal(&[], &[], 0.0));
+    }
+
+    #[test]
+    fn mediane_geometrique_resiste_aux_valeurs_aberrantes() {
+        let mut vecteurs = Vec::new();
+        for x in -1..=1 {
+            for y in -1..=1 {
+                vecteurs.push(vec![x as f32, y as f32]);
+            }
+        }
+        vecteurs.push(vec![1000.0, 1000.0]);
+        let db = base(&vecteurs);
+        let mediane = db.mediane_geometrique(200).expect("base non vide");
+        assert!(norme(&mediane) < 0.5, "médiane : {:?}", mediane);
+        assert!(norme(&db.centroide().unwrap()) > 100.0);
+
+        // Sur une droite, la médiane géométrique est la médiane ordinaire
+        let droite = base(&[vec![0.0], vec![1.0], vec![10.0]]);
+        let mediane = droite.mediane_geometrique(200).unwrap();
+        assert!((mediane[0] - 1.0).abs() < 1e-3, "médiane : {:?}", mediane);
+        assert!(Db::new().mediane_geometrique(10).is_none());
+    }
+
+    #[test]
+    fn index_colonnes_identique_bit_a_bit_a_la_recherche_par_lignes() {
+        let mut vecteurs = aleatoires(300, 48, 51);
+        vecteurs.extend(vecteurs[..10].to_vec());
+        vecteurs.push(vec![0.0; 48]);
+        let db = base(&vecteurs);
+        let index = db.index_colonnes();
+        for query in aleatoires(5, 48, 52).iter().chain(&vecteurs[..3]) {
+            let attendu = db.trouver_sans_compter(query, vecteurs.len(), TieBreak::Uuid);
+            let colonnes = index.trouver_les_plus_similaires(query, vecteurs.len());
+            assert_eq!(uuids(&colonnes), uuids(&attendu));
+            for ((_, score), (_, score_attendu)) in colonnes.iter().zip(&attendu) {
+                assert_eq!(score.to_bits(), score_attendu.to_bits());
+            }
+            assert_eq!(index.trouver_les_plus_similaires(query, 7), attendu[..7].to_vec());
+        }
+        assert!(Db::new().index_colonnes().trouver_les_plus_similaires(&[], 3).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "la requête doit avoir la dimension de l'index")]
+    fn index_colonnes_panique_sur_une_requete_de_mauvaise_dimension() {
+        base(&[vec![1.0, 0.0]]).index_colonnes().trouver_les_plus_similaires(&[1.0], 1);
+    }
+
+    /// Compare les temps de recherche des stockages par lignes et par colonnes en dimension 768.
+    /// 
+    /// À lancer en mode optimisé : `cargo test --release -- --ignored --nocapture chronometrage`.
+    #[test]
+    #[ignore]
+    fn chronometrage_index_colonnes_contre_lignes_en_dimension_768() {
+        use std::time::Instant;
+
+        let db = base(&aleatoires(20_000, 768, 53));
+        let requetes = aleatoires(20, 768, 54);
+        let (lignes, colonnes) = (db.index_matriciel(), db.index_colonnes());
+
+        let debut = Instant::now();
+        for query in &requetes {
+            lignes.trouver_les_plus_similaires(query, 10);
+        }
+        let duree_lignes = debut.elapsed();
+
+        let debut = Instant::now();
+        for query in &requetes {
+            colonnes.trouver_les_plus_similaires(query, 10);
+        }
+        let duree_colonnes = debut.elapsed();
+
+        println!(
+            "20 000 vecteurs × 768 dimensions, {} requêtes : lignes {:?}, colonnes {:?}",
+            requetes.len(),
+            duree_lignes,
+            duree_colonnes
+        );
+    }
+
+    #[test]
+    fn consultations_comptees_par_les_seules_recherches_servies() {
+        let db = base(&[vec![1.0, 0.0], vec![0.9, 0.1], vec![0.0, 1.0]]);
+        let query = [1.0, 0.0];
+        db.trouver_les_plus_similaires(&query, 2);
+        db.query(&query).limit(1).run();
+        assert_eq!((db.consultations(&id(1)), db.consultations(&id(2)), db.consultations(&id(3))), (2, 1, 0));
+
+        // Les analyses ne modifient pas les compteurs
+        db.trouver_sans_compter(&query, 3, TieBreak::Uuid);
+        db.seuil_pour_rappel(&[query.to_vec()], 0.9, 3);
+        db.plus_centraux(3);
+        db.evaluer(&query, &HashSet::from([id(1)]), &[1, 3]);
+        db.trouver_classe(&query, 3);
+        assert_eq!((db.consultations(&id(1)), db.consultations(&id(2)), db.consultations(&id(3))), (2, 1, 0));
+
+        // Les recherches spécialisées comptent, y compris chaque palier
+        db.mmr(&query, 1, 0.5);
+        db.trouver_par_produit_scalaire(&query, 2);
+        assert_eq!((db.consultations(&id(1)), db.consultations(&id(2)), db.consultations(&id(3))), (4, 2, 0));
+        db.trouver_par_paliers(&query, &[0.5, -0.5]);
+        assert_eq!((db.consultations(&id(1)), db.consultations(&id(2)), db.consultations(&id(3))), (5, 3, 1));
+    }
+
+    #[test]
+    fn conserver_top_consultes_supprime_les_moins_demandes() {
+        let mut db = base(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![-1.0, 0.0], vec![0.0, -1.0]]);
+        for _ in 0..3 {
+            db.trouver_les_plus_similaires(&[0.0, 1.0], 1);
+        }
+        db.trouver_les_plus_similaires(&[-1.0, 0.0], 1);
+        // id(1) et id(4) n'ont jamais été retournés : id(4) part d'abord (départage par UUID)
+        assert_eq!(db.conserver_top_consultes(3), 1);
+        assert!(!db.inner.contains(&id(4)) && db.inner.contains(&id(1)));
+        assert_eq!(db.conserver_top_consultes(2), 1);
+        let mut restants: Vec<Uuid> = db.inner.keys().copied().collect();
+        restants.sort();
+        assert_eq!(restants, vec![id(2), id(3)]);
+        assert_eq!(db.conserver_top_consultes(10), 0);
+    }
+
+    #[test]
+    fn trouver_adaptatif_ne_garde_que_les_resultats_detaches() {
+        let mut vecteurs = vec![vec![1.0, 0.0]];
+        vecteurs.extend((0..9).map(|_| vec![0.0, 1.0]));
+        let db = base(&vecteurs);
+        assert_eq!(uuids(&db.trouver_adaptatif(&[1.0, 0.0], 5, 0.5)), vec![id(1)]);
+        assert_eq!(db.trouver_adaptatif(&[1.0, 0.0], 5, 10.0).len(), 5);
+        assert!(Db::new().trouver_adaptatif(&[1.0], 5, 1.0).is_empty());
+    }
+
+    #[test]
+    fn poids_idf_nul_pour_une_dimension_commune() {
+        let db = base(&[vec![1.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0]]);
+        let poids = db.calcul_poids_idf();
+        assert_eq!(poids[0], 0.0);
+        assert!((poids[1] - 2.0f32.ln()).abs() < 1e-6);
+        assert!(Db::new().calcul_poids_idf().is_empty());
+
+        // Avec ces poids, seule la dimension rare compte
+        let resultats = db.trouver_avec_poids(&[1.0, 1.0], 3, &poids);
+        assert_eq!(resultats[0].0, id(3));
+        assert!((resultats[0].1 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "les poids doivent être positifs ou nuls")]
+    fn trouver_avec_poids_panique_sur_un_poids_negatif() {
+        base(&[vec![1.0, 0.0]]).trouver_avec_poids(&[1.0, 0.0], 1, &[1.0, -1.0]);
+    }
+
+    #[test]
+    fn empreinte_independante_de_l_ordre_d_insertion() {
+        let vecteurs = aleatoires(20, 3, 30);
+        let directe = base(&vecteurs);
+        let mut inverse = Db::new();
+        for (i, vecteur) in vecteurs.iter().enumerate().rev() {
+            inverse.insert(id(i as u128 + 1), vecteur.clone());
+        }
+        assert_eq!(directe.empreinte(), inverse.empreinte());
+
+        // Insérer puis supprimer un vecteur ramène l'empreinte
+        inverse.insert(id(100), vec![1.0, 2.0, 3.0]);
+        assert_ne!(directe.empreinte(), inverse.empreinte());
+        inverse.remove(&id(100));
+        assert_eq!(directe.empreinte(), inverse.empreinte());
+
+        // Une seule composante modifiée change l'empreinte
+        let mut modifiee = vecteurs.clone();
+        modifiee[0][0] = f32::from_bits(modifiee[0][0].to_bits() ^ 1);
+        assert_ne!(base(&modifiee).empreinte(), directe.empreinte());
+        assert_eq!(Db::new().empreinte(), 0);
+    }
+
+    #[test]
+    fn trouver_dans_bande_inclut_les_bornes() {
+        let db = base(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0], vec![-1.0, 0.0]]);
+        let resultats = db.trouver_dans_bande(&[1.0, 0.0], 0.0, 0.9, 10);
+        assert_eq!(uuids(&resultats), vec![id(3), id(2)]);
+        assert_eq!(db.trouver_dans_bande(&[1.0, 0.0], 0.0, 0.9, 1).len(), 1);
+        assert_eq!(uuids(&db.trouver_dans_bande(&[1.0, 0.0], 1.0, 1.0, 10)), vec![id(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "la borne basse de la bande doit être inférieure à la borne haute")]
+    fn trouver_dans_bande_panique_sur_une_bande_inversee() {
+        base(&[vec![1.0]]).trouver_dans_bande(&[1.0], 0.9, 0.1, 1);
+    }
+
+    #[test]
+    fn interpoler_relie_exactement_les_extremites() {
+        let db = base(&[vec![0.1, 0.2], vec![0.7, -0.3]]);
+        let points = db.interpoler(&id(1), &id(2), 7).expect("UUIDs présents");
+        assert_eq!(points.len(), 7);
+        assert_eq!(points[0], vec![0.1, 0.2]);
+        assert_eq!(points[6], vec![0.7, -0.3]);
+
+        let droite = base(&[vec![0.0, 0.0], vec![2.0, 4.0]]);
+        assert_eq!(droite.interpoler(&id(1), &id(2), 3), Some(vec![vec![0.0, 0.0], vec![1.0, 2.0], vec![2.0, 4.0]]));
+        assert_eq!(droite.interpoler(&id(1), &id(2), 1), Some(vec![vec![0.0, 0.0]]));
+        assert!(droite.interpoler(&id(1), &id(3), 3).is_none());
+    }
+
+    #[test]
+    fn entropie_requete_entre_zero_et_ln_n() {
+        let nette = base(&[vec![1.0, 0.0], vec![-1.0, 0.0], vec![0.0, -1.0]]);
+        assert!(nette.entropie_requete(&[1.0, 0.0], 0.01) < 1e-3);
+
+        let uniforme = base(&[vec![1.0, 0.0], vec![1.0, 0.0], vec![1.0, 0.0], vec![1.0, 0.0]]);
+        assert!((uniforme.entropie_requete(&[1.0, 0.0], 0.05) - 4.0f32.ln()).abs() < 1e-5);
+
+        // Une température plus élevée aplatit la distribution
+        let db = base(&aleatoires(30, 4, 31));
+        let query = [1.0, 0.0, 0.0, 0.0];
+        assert!(db.entropie_requete(&query, 0.05) < db.entropie_requete(&query, 1.0));
+        assert!(db.entropie_requete(&query, 1.0) <= 30.0f32.ln() + 1e-5);
+        assert_eq!(Db::new().entropie_requete(&query, 1.0), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "la température doit être strictement positive")]
+    fn entropie_requete_panique_sans_temperature() {
+        base(&[vec![1.0]]).entropie_requete(&[1.0], 0.0);
+    }
+
+    #[test]
+    fn insert_si_nouveau_ignore_les_quasi_doublons() {
+        let mut db = base(&[vec![1.0, 0.0]]);
+        assert!(!db.insert_si_nouveau(id(2), vec![1.0, 0.01], 0.99));
+        assert_eq!(db.inner.len(), 1);
+        assert!(db.insert_si_nouveau(id(2), vec![0.0, 1.0], 0.99));
+        assert_eq!(db.inner.len(), 2);
+        // Le seuil est inclusif
+        assert!(!db.insert_si_nouveau(id(3), vec![2.0, 0.0], 1.0));
+    }
+
+    #[test]
+    fn percentile_du_meilleur_resultat_vaut_un() {
+        let db = base(&aleatoires(40, 4, 32));
+        let query = [0.5, -0.2, 0.1, 0.9];
+        let resultats = db.trouver_avec_percentile(&query, 5);
+        assert_eq!(resultats[0].2, 1.0);
+        assert!(resultats.windows(2).all(|paire| paire[0].2 > paire[1].2));
+        let simples = db.trouver_sans_compter(&query, 5, TieBreak::Uuid);
+        for ((uuid, score, percentile), attendu) in resultats.iter().zip(&simples) {
+            assert_eq!((*uuid, *score), *attendu);
+            let range = db.inner.iter().filter(|(_, v)| calcul_similarite(&query, v) <= *score).count();
+            assert_eq!(*percentile, range as f32 / 40.0);
+        }
+    }
+
+    #[test]
+    fn positif_negatif_penalise_la_proximite_au_negatif() {
+        let db = base(&[vec![1.0, 0.0], vec![0.8, -0.6], vec![0.8, 0.6]]);
+        let resultats = db.trouver_positif_negatif(&[1.0, 0.0], &[0.8, 0.6], 3, 0.5);
+        assert_eq!(uuids(&resultats), vec![id(2), id(1), id(3)]);
+        assert!((resultats[0].1 - 0.66).abs() < 1e-5);
+        // Sans pénalité, c'est une recherche simple sur l'exemple positif
+        assert_eq!(
+            db.trouver_positif_negatif(&[1.0, 0.0], &[0.8, 0.6], 3, 0.0),
+            db.trouver_sans_compter(&[1.0, 0.0], 3, TieBreak::Uuid)
+        );
+    }
+
+    #[test]
+    fn classer_par_centroide_choisit_le_plus_proche() {
+        let db = Db::new();
+        let centroides = vec![
+            ("est".to_string(), vec![1.0, 0.0]),
+            ("nord".to_string(), vec![0.0, 1.0]),
+            ("nord-bis".to_string(), vec![0.0, 1.0]),
+        ];
+        assert_eq!(db.classer_par_centroide(&[0.9, 0.2], &centroides, Metric::Cosine), Some("est".to_string()));
+        assert_eq!(db.classer_par_centroide(&[0.0, 3.0], &centroides, Metric::Euclidean), Some("nord".to_string()));
+        assert_eq!(db.classer_par_centroide(&[1.0, 0.0], &[], Metric::Cosine), None);
+    }
+
+    #[test]
+    fn remove_many_compte_les_seules_suppressions_effectives() {
+        let mut db = base(&[vec![1.0], vec![2.0], vec![3.0]]);
+        assert_eq!(db.remove_many(vec![id(1), id(9), id(3), id(1)]), 2);
+        assert_eq!(db.inner.len(), 1);
+        assert!(db.inner.contains(&id(2)));
+    }
+
+    #[test]
+    fn projection_2d_preserve_les_groupes() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut db = Db::new();
+        for i in 0..20u128 {
+            let centre = if i < 10 { 5.0 } else { -5.0 };
+            db.insert(id(i), (0..5).map(|_| centre + rng.gen_range(-0.5..0.5)).collect());
+        }
+        let positions = db.projeter_2d(5.0, 300, 42);
+        assert_eq!(positions.len(), 20);
+        assert!(positions.windows(2).all(|paire| paire[0].0 < paire[1].0));
+
+        let centre = |groupe: &[(Uuid, [f32; 2])]| {
+            let somme = groupe.iter().fold([0.0f32; 2], |s, (_, p)| [s[0] + p[0], s[1] + p[1]]);
+            [somme[0] / groupe.len() as f32, somme[1] / groupe.len() as f32]
+        };
+        let (premier, second) = (centre(&positions[..10]), centre(&positions[10..]));
+        for (i, (_, point)) in positions.iter().enumerate() {
+            let (propre, autre) = if i < 10 { (premier, second) } else { (second, premier) };
+            assert!(distance_euclidienne(point, &propre) < distance_euclidienne(point, &autre));
+        }
+        assert_eq!(db.projeter_2d(5.0, 300, 42), positions);
+    }
+
+    #[test]
+    fn scores_quantifies_suivent_l_ordre_du_cosinus() {
+        let db = base(&[vec![1.0, 2.0], vec![2.0, 1.0], vec![-1.0, -2.0]]);
+        let resultats = db.trouver_les_plus_similaires_quantise(&[1.0, 2.0], 3, 1000);
+        assert_eq!(resultats[0], (id(1), 1000));
+        assert_eq!(resultats[1], (id(2), 800));
+        assert_eq!(resultats[2], (id(3), -1000));
+    }
+
+    #[test]
+    fn distance_moyenne_voisin_sur_une_grille() {
+        let mut grille = Vec::new();
+        for x in 0..3 {
+            for y in 0..3 {
+                grille.push(vec![x as f32, y as f32]);
+            }
+        }
+        let db = base(&grille);
+        assert!((db.distance_moyenne_voisin(Metric::Euclidean).unwrap() - 1.0).abs() < 1e-6);
+        assert!(base(&[vec![1.0]]).distance_moyenne_voisin(Metric::Euclidean).is_none());
+    }
+
+    #[test]
+    fn insert_text_retrouve_le_meme_texte() {
+        let embedder = EmbedderHachage { dimension: 64 };
+        let mut db = Db::new();
+        let chat = db.insert_text("Le chat dort", &embedder);
+        db.insert_text("un avion décolle ce matin", &embedder);
+        let resultats = db.trouver_sans_compter(&embedder.embed("le CHAT dort"), 1, TieBreak::Uuid);
+        assert_eq!(resultats[0].0, chat);
+        assert!((resultats[0].1 - 1.0).abs() < 1e-6);
+        assert_eq!(embedder.embed("").len(), 64);
+    }
+
+    #[test]
+    fn evaluer_precision_et_rappel_par_k() {
+        let angle = |degres: f32| vec![degres.to_radians().cos(), degres.to_radians().sin()];
+        let db = base(&[angle(0.0), angle(10.0), angle(20.0), angle(30.0)]);
+        let pertinents = HashSet::from([id(1), id(3)]);
+        let mesures = db.evaluer(&angle(0.0), &pertinents, &[3, 1, 0]);
+        assert_eq!(mesures[0].0, 3);
+        assert!((mesures[0].1 - 2.0 / 3.0).abs() < 1e-6 && (mesures[0].2 - 1.0).abs() < 1e-6);
+        assert_eq!(mesures[1], (1, 1.0, 0.5));
+        assert_eq!(mesures[2], (0, 0.0, 0.0));
+        assert_eq!(db.evaluer(&angle(0.0), &HashSet::new(), &[2])[0].2, 0.0);
+    }
+
+    #[test]
+    fn fenetre_db_purge_les_entrees_perimees() {
+        let mut fenetre = FenetreDb::new(Duration::from_secs(3600));
+        let maintenant = SystemTime::now();
+        fenetre.insert_avec_horodatage(id(1), vec![1.0, 0.0], maintenant - Duration::from_secs(7200));
+        fenetre.insert(id(2), vec![0.0, 1.0]);
+        assert_eq!(fenetre.purger(), 1);
+        assert!(!fenetre.db().inner.contains(&id(1)));
+        assert!(fenetre.db().inner.contains(&id(2)));
+
+        // Une entrée datée exactement de la limite est conservée
+        let limite = maintenant - Duration::from_secs(60);
+        fenetre.insert_avec_horodatage(id(3), vec![1.0, 1.0], limite);
+        assert_eq!(fenetre.purger_avant(limite), 0);
+        assert_eq!(fenetre.purger_avant(limite + Duration::from_nanos(1)), 1);
+        assert_eq!(fenetre.db().inner.len(), 1);
+    }
+
+    #[test]
+    fn affectation_hongroise_optimum_connu() {
+        let couts = vec![vec![4.0, 1.0, 3.0], vec![2.0, 0.0, 5.0], vec![3.0, 2.0, 2.0]];
+        assert_eq!(affectation_hongroise(&couts), vec![1, 0, 2]);
+
+        // Moins de lignes que de colonnes : chaque ligne reçoit une colonne distincte
+        let rectangulaire = vec![vec![1.0, 2.0, 3.0], vec![3.0, 1.0, 2.0]];
+        assert_eq!(affectation_hongroise(&rectangulaire), vec![0, 1]);
+    }
+
+    /// Toutes les permutations de `0..n`, pour vérifier l'algorithme hongrois par force brute.
+    fn permutations(n: usize) -> Vec<Vec<usize>> {
+        if n == 0 {
+            return vec![Vec::new()];
+        }
+        let mut resultat = Vec::new();
+        for permutation in permutations(n - 1) {
+            for position in 0..=permutation.len() {
+                let mut suivante = permutation.clone();
+                suivante.insert(position, n - 1);
+                resultat.push(suivante);
+            }
+        }
+        resultat
+    }
+
+    #[test]
+    fn affectation_hongroise_egale_la_force_brute() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..20 {
+            let couts: Vec<Vec<f64>> = (0..5).map(|_| (0..5).map(|_| rng.gen_range(0.0..10.0)).collect()).collect();
+            let cout = |affectation: &[usize]| -> f64 {
+                affectation.iter().enumerate().map(|(i, &j)| couts[i][j]).sum()
+            };
+            let optimum = permutations(5).iter().map(|p| cout(p)).fold(f64::INFINITY, f64::min);
+            let affectation = affectation_hongroise(&couts);
+            let distinctes: HashSet<usize> = affectation.iter().copied().collect();
+            assert_eq!(distinctes.len(), 5);
+            assert!((cout(&affectation) - optimum).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn appariement_optimal_bat_l_appariement_glouton() {
+        // Un appariement glouton prendrait (a2, b1) à 0.99 puis (a1, b2) à 0.0
+        let gauche = base(&[vec![1.0, 0.0], vec![FRAC_1_SQRT_2, FRAC_1_SQRT_2]]);
+        let droite = base(&[vec![0.8, 0.6], vec![0.0, 1.0]]);
+        let paires = gauche.appariement_optimal(&droite);
+        assert_eq!(paires.iter().map(|(a, b, _)| (*a, *b)).collect::<Vec<_>>(), vec![(id(1), id(1)), (id(2), id(2))]);
+        let total: f32 = paires.iter().map(|(_, _, s)| s).sum();
+        assert!((total - 1.507).abs() < 1e-3);
+
+        // Avec une base plus grande, ses vecteurs en surplus restent sans partenaire
+        let grande = base(&[vec![0.0, 1.0], vec![-1.0, 0.0], vec![1.0, 0.1]]);
+        let paires = base(&[vec![1.0, 0.0]]).appariement_optimal(&grande);
+        assert_eq!(paires.len(), 1);
+        assert_eq!((paires[0].0, paires[0].1), (id(1), id(3)));
+    }
+
+    #[test]
+    fn variance_expliquee_par_une_seule_direction() {
+        let mut rng = StdRng::seed_from_u64(43);
+        let droite: Vec<Vec<f32>> = (0..100)
+            .map(|_| {
+                let t: f32 = rng.gen_range(-10.0..10.0);
+                (1..=4).map(|k| k as f32 * t + rng.gen_range(-0.01..0.01)).collect()
+            })
+            .collect();
+        let parts = base(&droite).variance_expliquee(4);
+        assert_eq!(parts.len(), 4);
+        assert!(parts[0] > 0.999, "{:?}", parts);
+        assert!(parts.windows(2).all(|paire| paire[0] <= paire[1] + 1e-6));
+        assert!((parts[3] - 1.0).abs() < 1e-3);
+        assert!(Db::new().variance_expliquee(3).is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn recherche_parallele_identique_a_la_recherche_sequentielle() {
+        let mut vecteurs = aleatoires(500, 8, 48);
+        vecteurs.extend(vecteurs[..20].to_vec());
+        let db = base(&vecteurs);
+        db.prechauffer();
+        for query in &vecteurs[..5] {
+            assert_eq!(
+                db.trouver_les_plus_similaires_parallele(query, 30),
+                db.trouver_sans_compter(query, 30, TieBreak::Uuid)
+            );
+        }
+    }
+
+    #[test]
+    fn centroides_par_groupe_simples_et_ponderes() {
+        let mut db = Db::new();
+        let entrees = [("a", vec![0.0, 0.0], "1"), ("a", vec![2.0, 2.0], "3"), ("b", vec![5.0, 5.0], "0")];
+        for (i, (groupe, vecteur, poids)) in entrees.iter().enumerate() {
+            let mut metadonnees = Metadonnees::new();
+            metadonnees.insert("groupe".to_string(), groupe.to_string());
+            metadonnees.insert("poids".to_string(), poids.to_string());
+            db.insert_avec_metadonnees(id(i as u128), vecteur.clone(), metadonnees);
+        }
+        db.insert(id(10), vec![9.0, 9.0]);
+
+        let simples = db.centroides_par_groupe(|m| m["groupe"].clone());
+        assert_eq!(simples.len(), 2);
+        assert_eq!(simples["a"], vec![1.0, 1.0]);
+        assert_eq!(simples["b"], vec![5.0, 5.0]);
+
+        let ponderes = db.centroides_par_groupe_pondere(
+            |m| m["groupe"].clone(),
+            |_, m| m["poids"].parse().unwrap(),
+        );
+        // Le groupe « b », de poids total nul, est écarté
+        assert_eq!(ponderes.len(), 1);
+        assert_eq!(ponderes["a"], vec![1.5, 1.5]);
+
+        let uniformes = db.centroides_par_groupe_pondere(|m| m["groupe"].clone(), |_, _| 2.0);
+        assert_eq!(uniformes, simples);
+    }
+
+    #[test]
+    #[should_panic(expected = "les poids doivent être finis et positifs ou nuls")]
+    fn centroides_par_groupe_pondere_panique_sur_un_poids_negatif() {
+        let mut db = Db::new();
+        db.insert_avec_metadonnees(id(1), vec![1.0], Metadonnees::new());
+        db.centroides_par_groupe_pondere(|_| 0, |_, _| -1.0);
+    }
+
+    #[test]
+    fn facteur_aberrant_local_isole_le_point_eloigne() {
+        let mut points = Vec::new();
+        for x in 0..5 {
+            for y in 0..5 {
+                points.push(vec![x as f32, y as f32]);
+            }
+        }
+        points.push(vec![20.0, 20.0]);
+        let facteurs = base(&points).facteur_aberrant_local(5);
+        assert_eq!(facteurs.len(), 26);
+        let (pire, facteur) = facteurs.iter().max_by(|a, b| a.1.total_cmp(b.1)).unwrap();
+        assert_eq!(*pire, id(26));
+        assert!(*facteur > 2.0);
+        assert!(facteurs.iter().filter(|(uuid, _)| **uuid != id(26)).all(|(_, &f)| f < 1.5));
+        assert!(base(&[vec![1.0]]).facteur_aberrant_local(3).is_empty());
+    }
+
+    #[test]
+    fn ecriture_filtree_et_relecture() {
+        let mut db = Db::new();
+        for i in 0..6u128 {
+            let mut metadonnees = Metadonnees::new();
+            metadonnees.insert("rang".to_string(), i.to_string());
+            db.insert_avec_metadonnees(id(i), vec![i as f32, 1.0], metadonnees);
+        }
+        let mut octets = Vec::new();
+        db.ecrire_filtre(&mut octets, |uuid, _| uuid.as_u128() % 2 == 0).unwrap();
+        let relue = Db::lire(&mut octets.as_slice()).unwrap();
+        assert_eq!(relue.inner.len(), 3);
+        assert_eq!(relue.inner[&id(4)], vec![4.0, 1.0]);
+        assert_eq!(relue.metadonnees(&id(4)).and_then(|m| m.get("rang")), Some(&"4".to_string()));
+        assert!(!relue.inner.contains(&id(1)));
+
+        let chemin = std::env::temp_dir().join(format!("tprust-test-{}.db", std::process::id()));
+        db.save_subset(&chemin, |_, embedding| embedding[0] >= 3.0).unwrap();
+        let chargee = Db::load(&chemin).unwrap();
+        std::fs::remove_file(&chemin).unwrap();
+        assert_eq!(chargee.inner.len(), 3);
+        assert_eq!(chargee.empreinte(), {
+            let mut attendue = Db::new();
+            for i in 3..6u128 {
+                attendue.insert(id(i), vec![i as f32, 1.0]);
+            }
+            attendue.empreinte()
+        });
+    }
+
+    #[test]
+    fn trouver_classe_indexe_le_classement_par_rang() {
+        let db = base(&aleatoires(20, 3, 44));
+        let query = [1.0, 0.5, -0.5];
+        let classement = db.trouver_classe(&query, 5);
+        let attendu = db.trouver_sans_compter(&query, 5, TieBreak::Uuid);
+        assert_eq!(classement.into_iter().collect::<Vec<_>>(), attendu.into_iter().enumerate().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn inverser_matrice_et_matrice_singuliere() {
+        let inverse = inverser_matrice(&[vec![4.0, 7.0], vec![2.0, 6.0]]).expect("matrice inversible");
+        let attendue = [[0.6, -0.7], [-0.2, 0.4]];
+        for (ligne, attendue) in inverse.iter().zip(&attendue) {
+            for (x, y) in ligne.iter().zip(attendue) {
+                assert!((x - y).abs() < 1e-12);
+            }
+        }
+        assert!(inverser_matrice(&[vec![1.0, 2.0], vec![2.0, 4.0]]).is_none());
+    }
+
+    #[test]
+    fn mahalanobis_tient_compte_de_l_anisotropie() {
+        // Une base étirée le long de x : un écart en y pèse bien plus qu'un écart en x
+        let mut points = Vec::new();
+        for x in -10..=10 {
+            for y in [-0.1, 0.0, 0.1] {
+                points.push(vec![x as f32, y]);
+            }
+        }
+        let mut db = base(&points);
+        let (a, b) = (id(100), id(101));
+        db.insert(a, vec![3.0, 0.0]);
+        db.insert(b, vec![0.0, 1.0]);
+        let rang = |resultats: &[(Uuid, f32)], uuid: Uuid| resultats.iter().position(|(u, _)| *u == uuid).unwrap();
+
+        let query = [0.0, 0.0];
+        let euclidienne = db.query(&query).metric(Metric::Euclidean).run();
+        let euclidienne: Vec<(Uuid, f32)> = euclidienne.iter().map(|r| (r.uuid, r.score)).collect();
+        assert!(rang(&euclidienne, b) < rang(&euclidienne, a));
+
+        let sans_cache = db.trouver_mahalanobis(&query, 65);
+        assert!(rang(&sans_cache, a) < rang(&sans_cache, b));
+        assert!(sans_cache.windows(2).all(|paire| paire[0].1 <= paire[1].1));
+        db.distance_mahalanobis();
+        assert_eq!(db.trouver_mahalanobis(&query, 65), sans_cache);
+    }
+
+    #[test]
+    fn arbre_couvrant_minimal_sur_une_droite() {
+        let db = base(&[vec![0.0], vec![1.0], vec![3.0], vec![7.0]]);
+        assert_eq!(
+            db.arbre_couvrant_minimal(Metric::Euclidean),
+            vec![(id(1), id(2), 1.0), (id(2), id(3), 2.0), (id(3), id(4), 4.0)]
+        );
+        assert!(base(&[vec![1.0]]).arbre_couvrant_minimal(Metric::Euclidean).is_empty());
+    }
+
+    #[test]
+    fn paliers_ouverts_par_leur_seuil() {
+        let db = base(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![-1.0, 0.0], vec![1.0, 1.0]]);
+        let paliers = db.trouver_par_paliers(&[1.0, 0.0], &[1.0, 0.5, 0.0]);
+        assert_eq!(paliers.len(), 3);
+        assert_eq!(uuids(&paliers[0]), vec![id(1)]);
+        assert_eq!(uuids(&paliers[1]), vec![id(4)]);
+        assert_eq!(uuids(&paliers[2]), vec![id(2)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "les seuils des paliers doivent être strictement décroissants")]
+    fn paliers_paniquent_sur_des_seuils_non_decroissants() {
+        base(&[vec![1.0]]).trouver_par_paliers(&[1.0], &[0.5, 0.5]);
+    }
+
+    #[test]
+    fn statistiques_suivies_egalent_les_statistiques_recalculees() {
+        let vecteurs = aleatoires(25, 4, 38);
+        let mut suivie = Db::new().avec_statistiques();
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            suivie.insert(id(i as u128 + 1), vecteur.clone());
+        }
+        suivie.remove(&id(2));
+        suivie.insert(id(3), vec![0.5, 0.5, 0.5, 0.5]);
+
+        let mut recalculee = Db::new();
+        for (uuid, embedding) in suivie.inner.iter() {
+            recalculee.insert(*uuid, embedding.to_vec());
+        }
+        let (moyenne, ecart_type) = suivie.feature_stats().unwrap();
+        let (moyenne_attendue, ecart_type_attendu) = recalculee.feature_stats().unwrap();
+        assert!(moyenne.iter().zip(&moyenne_attendue).all(|(a, b)| (a - b).abs() < 1e-5));
+        assert!(ecart_type.iter().zip(&ecart_type_attendu).all(|(a, b)| (a - b).abs() < 1e-5));
+        assert!(Db::new().feature_stats().is_none());
+    }
+
+    #[test]
+    fn importance_dimensions_designe_la_dimension_extreme() {
+        let db = base(&[vec![0.0, 5.0, 1.0], vec![0.0, 5.0, 2.0], vec![0.0, 5.0, 3.0], vec![10.0, 5.0, 2.0]]);
+        let importance = db.importance_dimensions(&id(4), 3).unwrap();
+        assert_eq!(importance.iter().map(|(d, _)| *d).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert!((importance[0].1 - 7.5 / 18.75f32.sqrt()).abs() < 1e-5);
+        // Une dimension constante a un score nul
+        assert_eq!(importance[1].1, 0.0);
+        assert_eq!(db.importance_dimensions(&id(4), 1).unwrap().len(), 1);
+        assert!(db.importance_dimensions(&id(9), 3).is_none());
+    }
+
+    /// Index approché simulé : une requête de difficulté `d` ne retourne les vrais plus proches
+    /// voisins qu'à partir de `ef >= d`, et en deçà des résultats décalés qui changent avec `ef`.
+    struct IndexSimule<F: Fn(&[f32]) -> usize> {
+        db: Db,
+        difficulte: F,
+    }
+
+    impl<F: Fn(&[f32]) -> usize> RechercheParametree for IndexSimule<F> {
+        fn rechercher_ef(&self, query: &[f32], n: usize, ef: usize) -> Vec<(Uuid, f32)> {
+            let decalage = (self.difficulte)(query).saturating_sub(ef);
+            let classement = self.db.trouver_sans_compter(query, self.db.inner.len(), TieBreak::Uuid);
+            classement.into_iter().skip(decalage).take(n).collect()
+        }
+    }
+
+    #[test]
+    fn ef_adaptatif_plus_grand_pour_les_requetes_difficiles() {
+        let requetes = aleatoires(2, 8, 60);
+        let (facile, difficile) = (requetes[0].clone(), requetes[1].clone());
+        let index = IndexSimule {
+            db: base(&aleatoires(300, 8, 59)),
+            difficulte: |query: &[f32]| if query == difficile.as_slice() { 100 } else { 3 },
+        };
+        let exacts = |query: &[f32]| uuids(&index.db.trouver_sans_compter(query, 10, TieBreak::Uuid));
+
+        // ef = 4 suffit déjà, le tour à 8 le confirme
+        let resultat_facile = recherche_ef_adaptative(&index, &facile, 10, 4, 1024);
+        assert_eq!(resultat_facile.ef, 8);
+        assert_eq!(uuids(&resultat_facile.resultats), exacts(&facile));
+
+        // 4, 8, …, 64 donnent des résultats décalés ; 128 est le premier ef suffisant, 256 le confirme
+        let resultat_difficile = recherche_ef_adaptative(&index, &difficile, 10, 4, 1024);
+        assert_eq!(resultat_difficile.ef, 256);
+        assert_eq!(uuids(&resultat_difficile.resultats), exacts(&difficile));
+        assert!(resultat_difficile.ef > resultat_facile.ef);
+
+        // L'élargissement s'arrête à ef_max, même sans stabilisation
+        let plafonne = recherche_ef_adaptative(&index, &difficile, 10, 4, 48);
+        assert_eq!(plafonne.ef, 48);
+        assert_ne!(uuids(&plafonne.resultats), exacts(&difficile));
+        assert_eq!(recherche_ef_adaptative(&index, &facile, 10, 500, 64).ef, 64);
+    }
+
+    #[test]
+    fn difference_covariance_insensible_aux_translations() {
+        let vecteurs = aleatoires(50, 3, 45);
+        let translates: Vec<Vec<f32>> = vecteurs.iter().map(|v| v.iter().map(|x| x + 7.0).collect()).collect();
+        let etires: Vec<Vec<f32>> = vecteurs.iter().map(|v| v.iter().map(|x| x * 3.0).collect()).collect();
+        let reference = base(&vecteurs);
+        assert!(reference.difference_covariance(&base(&translates)).unwrap() < 1e-4);
+        assert!(reference.difference_covariance(&base(&etires)).unwrap() > 0.1);
+        assert!(reference.difference_covariance(&Db::new()).is_none());
+        assert!(reference.difference_covariance(&base(&[vec![1.0, 2.0]])).is_none());
+    }
+
+    #[test]
+    fn cosine_pondere_uniforme_egale_le_cosinus() {
+        for (query, stored) in aleatoires(10, 16, 3).iter().zip(aleatoires(10, 16, 4).iter()) {
+            let uniforme = cosine_pondere(query, stored, &[2.5; 16]);
+            assert!((uniforme - calcul_similarite(query, stored)).abs() < 1e-5);
+        }
+        // Un poids nul fait disparaître la dimension, comme un masque
+        let query = [1.0, 2.0, 3.0];
+        let stored = [3.0, -1.0, 7.0];
+        let pondere = cosine_pondere(&query, &stored, &[1.0, 1.0, 0.0]);
+        assert!((pondere - cosine_masquee(&query, &stored, &[true, true, false])).abs() < 1e-6);
+        assert_eq!(cosine_pondere(&query, &stored, &[0.0; 3]), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "il faut un poids par dimension des vecteurs")]
+    fn cosine_pondere_panique_sans_un_poids_par_dimension() {
+        cosine_pondere(&[1.0, 2.0], &[3.0, 4.0], &[1.0]);
+    }
+
+    #[test]
+    fn silhouette_par_point_negative_pour_un_point_mal_affecte() {
+        let (db, mut groupes) = deux_groupes(10, 18);
+        groupes.insert(id(1), 1); // id(1) appartient en réalité au groupe 0
+        let coefficients = db.silhouette_par_point(&groupes, Metric::Euclidean);
+        assert_eq!(coefficients.len(), 20);
+        assert!(coefficients[&id(1)] < 0.0);
+        assert!(coefficients.iter().filter(|(uuid, _)| **uuid != id(1)).all(|(_, &s)| s > 0.5));
+        let moyenne = coefficients.values().sum::<f32>() / 20.0;
+        assert!((db.silhouette(&groupes, Metric::Euclidean) - moyenne).abs() < 1e-6);
+    }
+
+    #[test]
+    fn esquisse_gk_respecte_l_erreur_de_rang() {
+        let mut valeurs: Vec<u32> = (0..10_000).collect();
+        valeurs.shuffle(&mut StdRng::seed_from_u64(40));
+        let mut esquisse = EsquisseGK::new(0.01);
+        assert!(esquisse.quantile(0.5).is_none());
+        for &valeur in &valeurs {
+            esquisse.ajouter(valeur as f32);
+        }
+
+        for i in 0..=20 {
+            let phi = i as f32 / 20.0;
+            let valeur = esquisse.quantile(phi).unwrap();
+            // Les valeurs sont 0..n : le rang (à partir de 1) de `valeur` est `valeur + 1`
+            let rang = valeur as f64 + 1.0;
+            let rang_exact = (phi as f64 * 10_000.0).ceil();
+            assert!((rang - rang_exact).abs() <= 0.01 * 10_000.0, "phi {} : {}", phi, valeur);
+        }
+        assert!(esquisse.tuples.len() < 1_000, "{} tuples", esquisse.tuples.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "epsilon doit être dans ]0, 1[")]
+    fn esquisse_gk_panique_sur_un_epsilon_invalide() {
+        EsquisseGK::new(0.0);
+    }
+
+    #[test]
+    fn quantiles_approx_proches_des_quantiles_exacts() {
+        let db = base(&aleatoires(2_000, 4, 41));
+        let query = [0.3, -0.7, 0.2, 0.1];
+        let mut scores: Vec<f32> = db.inner.values().map(|v| calcul_similarite(&query, v)).collect();
+        scores.sort_by(f32::total_cmp);
+
+        let phis = [0.0, 0.25, 0.5, 0.9, 1.0];
+        let quantiles = db.quantiles_approx(&query, &phis, 0.01);
+        assert_eq!(quantiles.iter().map(|(phi, _)| *phi).collect::<Vec<_>>(), phis.to_vec());
+        for (phi, score) in quantiles {
+            let rang = scores.iter().filter(|&&s| s <= score).count() as f32;
+            assert!((rang - (phi * 2_000.0).ceil()).abs() <= 0.01 * 2_000.0, "phi {} : {}", phi, score);
+        }
+        assert!(Db::new().quantiles_approx(&query, &phis, 0.01).is_empty());
+    }
+
+    #[test]
+    fn spherical_kmeans_groupe_par_direction() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut db = Db::new();
+        for i in 0..20u128 {
+            // Des normes très différentes dans chacune des deux directions
+            let longueur: f32 = rng.gen_range(0.1..100.0);
+            let bruit: f32 = rng.gen_range(-0.05..0.05);
+            let point = if i < 10 { vec![longueur, bruit * longueur] } else { vec![bruit * longueur, longueur] };
+            db.insert(id(i), point);
+        }
+        let groupes = db.spherical_kmeans(2, 50, 42);
+        assert!(groupes.windows(2).all(|paire| paire[0].0 < paire[1].0));
+        let (premier, second) = (groupes[0].1, groupes[10].1);
+        assert_ne!(premier, second);
+        assert!(groupes[..10].iter().all(|(_, g)| *g == premier));
+        assert!(groupes[10..].iter().all(|(_, g)| *g == second));
+        assert!(Db::new().spherical_kmeans(2, 10, 0).is_empty());
+    }
+
+    #[test]
+    fn masque_seuil_suit_le_filtre() {
+        let vecteurs = aleatoires(50, 6, 11);
+        let db = base(&vecteurs);
+        let query = &aleatoires(1, 6, 12)[0];
+        let seuil = 0.2;
+        let attendus = vecteurs.iter().filter(|v| calcul_similarite(query, v) >= seuil).count();
+
+        let (liste, masque) = db.masque_seuil(query, seuil);
+        assert!(liste.windows(2).all(|paire| paire[0] < paire[1]));
+        assert_eq!(masque.iter().filter(|&&garde| garde).count(), attendus);
+        for (uuid, garde) in liste.iter().zip(&masque) {
+            let embedding = db.inner.get(uuid).expect("UUID stocké");
+            assert_eq!(*garde, calcul_similarite(query, embedding) >= seuil);
+        }
+
+        // Le seuil est inclusif
+        let exact = base(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert_eq!(exact.masque_seuil(&[1.0, 0.0], 0.0).1, vec![true, true]);
+    }
+
+    #[test]
+    fn paires_les_plus_similaires_egale_la_force_brute() {
+        let mut vecteurs = aleatoires(30, 5, 13);
+        // Un quasi-doublon qui doit former la meilleure paire
+        let mut doublon = vecteurs[4].clone();
+        doublon[0] += 1e-3;
+        vecteurs.push(doublon);
+        let db = base(&vecteurs);
+
+        let mut toutes = Vec::new();
+        for i in 0..vecteurs.len() {
+            for j in i + 1..vecteurs.len() {
+                toutes.push((id(i as u128 + 1), id(j as u128 + 1), calcul_similarite(&vecteurs[i], &vecteurs[j])));
+            }
+        }
+        toutes.sort_by(|x, y| y.2.partial_cmp(&x.2).unwrap().then((x.0, x.1).cmp(&(y.0, y.1))));
+
+        let paires = db.paires_les_plus_similaires(4);
+        assert_eq!(paires, toutes[..4].to_vec());
+        assert_eq!((paires[0].0, paires[0].1), (id(5), id(31)));
+        assert!(db.paires_les_plus_similaires(0).is_empty());
+    }
+
+    #[test]
+    fn statistiques_courantes_restaurees_apres_ajout_puis_retrait() {
+        let vecteurs = aleatoires(30, 3, 37);
+        let mut statistiques = StatistiquesCourantes::default();
+        for vecteur in &vecteurs {
+            statistiques.ajouter(vecteur);
+        }
+        let (moyenne, ecart_type) = statistiques.moyenne_et_ecart_type().unwrap();
+
+        statistiques.ajouter(&[100.0, -50.0, 3.0]);
+        statistiques.retirer(&[100.0, -50.0, 3.0]);
+        let (moyenne_apres, ecart_type_apres) = statistiques.moyenne_et_ecart_type().unwrap();
+        assert!(moyenne.iter().zip(&moyenne_apres).all(|(a, b)| (a - b).abs() < 1e-5));
+        assert!(ecart_type.iter().zip(&ecart_type_apres).all(|(a, b)| (a - b).abs() < 1e-5));
+
+        for vecteur in &vecteurs {
+            statistiques.retirer(vecteur);
+        }
+        assert!(statistiques.moyenne_et_ecart_type().is_none());
+    }
+
+    #[test]
+    fn covariance_suivie_egale_la_covariance_recalculee() {
+        let vecteurs = aleatoires(40, 3, 39);
+        let mut suivie = Db::new().avec_covariance();
+        for (i, vecteur) in vecteurs.iter().enumerate() {
+            suivie.insert(id(i as u128 + 1), vecteur.clone());
+        }
+        let (moyenne, covariance) = suivie.matrice_covariance().unwrap();
+
+        // Insérer puis retirer un vecteur, ou l'écraser puis le rétablir, ramène la covariance
+        suivie.insert(id(100), vec![10.0, -10.0, 5.0]);
+        suivie.remove(&id(100));
+        suivie.insert(id(1), vec![3.0, 3.0, 3.0]);
+        suivie.insert(id(1), vecteurs[0].clone());
+        let (moyenne_apres, covariance_apres) = suivie.matrice_covariance().unwrap();
+        assert!(moyenne.iter().zip(&moyenne_apres).all(|(a, b)| (a - b).abs() < 1e-9));
+        for (ligne, ligne_apres) in covariance.iter().zip(&covariance_apres) {
+            assert!(ligne.iter().zip(ligne_apres).all(|(a, b)| (a - b).abs() < 1e-9));
+        }
+
+        let (moyenne_attendue, covariance_attendue) = base(&vecteurs).matrice_covariance().unwrap();
+        assert!(moyenne.iter().zip(&moyenne_attendue).all(|(a, b)| (a - b).abs() < 1e-4));
+        for (ligne, ligne_attendue) in covariance.iter().zip(&covariance_attendue) {
+            assert!(ligne.iter().zip(ligne_attendue).all(|(a, b)| (a - b).abs() < 1e-4));
+        }
+
+        let mut seule = CovarianceCourante::default();
+        seule.ajouter(&[1.0, 2.0]);
+        seule.retirer(&[1.0, 2.0]);
+        assert!(seule.moyenne_et_covariance().is_none());
+    }
+
+    #[test]
+    fn sous_ensemble_diversifie_couvre_chaque_groupe() {
+        let mut rng = StdRng::seed_from_u64(22);
+        let centres = [[0.0, 0.0], [50.0, 0.0], [0.0, 50.0]];
+        let mut db = Db::new();
+        for i in 0..30u128 {
+            let centre = centres[(i % 3) as usize];
+            db.insert(id(i), vec![centre[0] + rng.gen_range(-1.0..1.0), centre[1] + rng.gen_range(-1.0..1.0)]);
+        }
+        for seed in 0..5 {
+            let selection = db.sous_ensemble_diversifie(3, seed);
+            let groupes: HashSet<u128> = selection.iter().map(|uuid| uuid.as_u128() % 3).collect();
+            assert_eq!(groupes.len(), 3);
+        }
+        assert_eq!(db.sous_ensemble_diversifie(100, 0).len(), 30);
+    }
+
+    #[test]
+    fn produit_scalaire_valeur_calculee_a_la_main() {
+        assert_eq!(produit_scalaire(&[1.0, 2.0, 3.0], &[4.0, -5.0, 6.0]), 12.0);
+        // Seules les composantes communes comptent
+        assert_eq!(produit_scalaire(&[1.0, 2.0, 3.0], &[4.0, -5.0]), -6.0);
+    }
+
+    #[test]
+    fn produit_scalaire_brut_favorise_les_vecteurs_longs() {
+        let db = base(&[vec![1.0, 0.0], vec![3.0, 0.0], vec![0.0, 5.0]]);
+        let resultats = db.trouver_par_produit_scalaire(&[1.0, 0.5], 3);
+        assert_eq!(resultats, vec![(id(2), 3.0), (id(3), 2.5), (id(1), 1.0)]);
+        // Le cosinus, lui, ignore la norme
+        assert_eq!(db.trouver_les_plus_similaires(&[1.0, 0.5], 1)[0].0, id(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "le produit scalaire exige des vecteurs de la dimension de la requête")]
+    fn produit_scalaire_brut_panique_sur_une_dimension_differente() {
+        base(&[vec![1.0, 0.0, 0.0]]).trouver_par_produit_scalaire(&[1.0, 0.0], 1);
+    }
+
+    #[test]
+    fn hyperloglog_dans_sa_borne_d_erreur() {
+        let mut compteur = CompteurVoisinsDistincts::new(10, 12);
+        for i in 0..20_000u128 {
+            compteur.ajouter(&id(i));
+            // Les doublons ne changent pas l'estimation
+            compteur.ajouter(&id(i / 2));
+        }
+        let estimation = compteur.estimation_voisins_distincts() as f64;
+        let erreur_relative = (estimation - 20_000.0).abs() / 20_000.0;
+        assert!(erreur_relative < 3.0 * 1.04 / 64.0, "estimation : {}", estimation);
+        assert_eq!(CompteurVoisinsDistincts::new(10, 12).estimation_voisins_distincts(), 0);
+    }
+
+    #[test]
+    fn hyperloglog_compte_les_voisins_servis() {
+        let db = base(&aleatoires(40, 3, 35));
+        let requetes = aleatoires(25, 3, 36);
+        let mut compteur = CompteurVoisinsDistincts::new(3, 10);
+        let mut servis = HashSet::new();
+        for query in &requetes {
+            compteur.observer(&db, query);
+            servis.extend(uuids(&db.trouver_sans_compter(query, 3, TieBreak::Uuid)));
+        }
+        let estimation = compteur.estimation_voisins_distincts() as i64;
+        assert!((estimation - servis.len() as i64).abs() <= 2, "{} pour {}", estimation, servis.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "la précision doit être entre 4 et 16")]
+    fn hyperloglog_panique_sur_une_precision_invalide() {
+        CompteurVoisinsDistincts::new(10, 17);
+    }
+
+    #[test]
+    fn normaliser_scores_batch_min_max_et_z_score() {
+        let mut lots = vec![
+            vec![(id(1), 0.9), (id(2), 0.7), (id(3), 0.5)],
+            vec![(id(4), 0.2), (id(5), 0.2)],
+            Vec::new(),
+        ];
+        normaliser_scores_batch(&mut lots, NormalisationScores::MinMax);
+        assert_eq!(lots[0][0].1, 1.0);
+        assert_eq!(lots[0][2].1, 0.0);
+        assert!((lots[0][1].1 - 0.5).abs() < 1e-6);
+        assert_eq!(lots[1], vec![(id(4), 1.0), (id(5), 1.0)]);
+        assert!(lots[2].is_empty());
+
+        let mut lots = vec![vec![(id(1), 3.0), (id(2), 1.0)], vec![(id(3), 0.4), (id(4), 0.4)]];
+        normaliser_scores_batch(&mut lots, NormalisationScores::ZScore);
+        assert_eq!(lots[0], vec![(id(1), 1.0), (id(2), -1.0)]);
+        assert_eq!(lots[1], vec![(id(3), 0.0), (id(4), 0.0)]);
+    }
+
+    #[test]
+    fn seuil_pour_rappel_baisse_quand_le_rappel_vise_monte() {
+        let db = base(&aleatoires(200, 8, 14));
+        let requetes = aleatoires(20, 8, 15);
+        let haut = db.seuil_pour_rappel(&requetes, 0.95, 10);
+        let bas = db.seuil_pour_rappel(&requetes, 0.3, 10);
+        assert!(haut < bas, "seuil pour 95 % : {}, pour 30 % : {}", haut, bas);
+
+        // Le seuil retient bien au moins la fraction visée du top-n
+        let retenus: usize = requetes
+            .iter()
+            .map(|q| db.trouver_sans_compter(q, 10, TieBreak::Uuid).iter().filter(|(_, s)| *s >= haut).count())
+            .sum();
+        assert!(retenus as f32 >= 0.95 * 200.0 - 1e-3);
+
+        assert_eq!(Db::new().seuil_pour_rappel(&requetes, 0.9, 10), -1.0);
+    }
+
+    #[test]
+    fn fusionner_resultats_garde_le_meilleur_score() {
+        let resultats = vec![
+            vec![(id(1), 0.5), (id(2), 0.9)],
+            vec![(id(1), 0.8), (id(3), 0.1)],
+            vec![(id(2), 0.3)],
+        ];
+        assert_eq!(fusionner_resultats(&resultats, 10), vec![(id(2), 0.9), (id(1), 0.8), (id(3), 0.1)]);
+        assert_eq!(fusionner_resultats(&resultats, 1), vec![(id(2), 0.9)]);
+    }
+
+    #[test]
+    fn rrf_favorise_les_uuids_presents_dans_plusieurs_listes() {
+        let (a, b, c) = (id(1), id(2), id(3));
+        let top = rrf(&[vec![b, a], vec![c, a]], 60, 3);
+        assert_eq!(top[0].0, a);
+        assert!((top[0].1 - (1.0 / 62.0 + 1.0 / 62.0)).abs() < 1e-7);
+        // b et c, premiers d'une seule liste, sont à égalité : départage par UUID
+        assert_eq!(uuids(&top[1..]), vec![b, c]);
+
+        // Seule la meilleure position d'un UUID dans un même classement compte
+        let doublon = rrf(&[vec![a, a, b]], 60, 2);
+        assert_eq!(doublon, vec![(a, 1.0 / 61.0), (b, 1.0 / 63.0)]);
+    }
+}